@@ -0,0 +1,226 @@
+//! Checking the geometry of a running job against its allocation.
+use serde::Serialize;
+
+use crate::launcher::{parse_count, SlurmOptions};
+use crate::{Geometry, NodeTopology, ParseError, Reservation};
+
+/// The geometry of a running Slurm or PBS job, and the ways in which it does
+/// not match the allocation the job is running in.
+#[derive(Clone, Debug, Serialize)]
+pub struct AllocationCheck {
+    /// The scheduler the job is running under, `slurm` or `pbs`.
+    pub scheduler: &'static str,
+    /// The geometry of the job.
+    pub geometry: Geometry,
+    /// The number of nodes in the allocation.
+    pub nodes: u32,
+    /// The number of logical CPUs the allocation provides on each node, if
+    /// the scheduler reports it.
+    pub cpus_on_node: Option<u32>,
+    /// The number of OpenMP threads each PE will start, if set.
+    pub omp_threads: Option<u32>,
+    /// Descriptions of the ways the geometry does not match the allocation.
+    pub mismatches: Vec<String>,
+}
+
+/// Rebuild the geometry of a running job from the environment variables set
+/// by its scheduler, and check it against the allocation.
+///
+/// Slurm jobs are described by `SLURM_NTASKS`, `SLURM_CPUS_PER_TASK`,
+/// `SLURM_NTASKS_PER_NODE`, `SLURM_JOB_NUM_NODES` and `SLURM_CPUS_ON_NODE`.
+/// PBS jobs are described by `NCPUS` or `PBS_NUM_PPN`, `PBS_NUM_NODES` and
+/// `PBS_NP`, with the file named by `PBS_NODEFILE` read to count the PEs and
+/// nodes when these are not set. In both cases `OMP_NUM_THREADS` gives the
+/// number of threads each PE starts.
+///
+/// The environment is read through `lookup`, which returns the value of a
+/// variable if it is set, so the check can be run against an environment
+/// other than the current process's.
+///
+/// # Arguments
+///
+/// * `lookup` - Returns the value of an environment variable.
+/// * `topology` - The layout of the CPUs within each node.
+///
+/// # Examples
+/// ```
+/// use pestr::{allocation, NodeTopology};
+/// let lookup = |name: &str| match name {
+///     "SLURM_JOB_NUM_NODES" => Some(String::from("64")),
+///     "SLURM_NTASKS" => Some(String::from("512")),
+///     "SLURM_CPUS_PER_TASK" => Some(String::from("16")),
+///     "SLURM_CPUS_ON_NODE" => Some(String::from("128")),
+///     "OMP_NUM_THREADS" => Some(String::from("8")),
+///     _ => None,
+/// };
+/// let topology = NodeTopology::new(1, 1, 128, 1).unwrap();
+/// let check = allocation::check_allocation(lookup, topology).unwrap();
+/// assert_eq!((check.geometry.tasks, check.geometry.threads), (512, 16));
+/// assert_eq!(
+///     check.mismatches,
+///     ["OMP_NUM_THREADS is 8 but each PE has 16 CPUs (SLURM_CPUS_PER_TASK)"]
+/// );
+/// ```
+///
+/// A job that needs fewer nodes than it was allocated:
+/// ```
+/// use pestr::{allocation, NodeTopology};
+/// let lookup = |name: &str| match name {
+///     "SLURM_JOB_ID" => Some(String::from("1")),
+///     "SLURM_JOB_NUM_NODES" => Some(String::from("20")),
+///     "SLURM_NTASKS" => Some(String::from("128")),
+///     "SLURM_CPUS_PER_TASK" => Some(String::from("8")),
+///     "OMP_NUM_THREADS" => Some(String::from("8")),
+///     _ => None,
+/// };
+/// let topology = NodeTopology::new(1, 1, 128, 1).unwrap();
+/// let check = allocation::check_allocation(lookup, topology).unwrap();
+/// assert_eq!(check.mismatches, ["the job needs 8 nodes but the allocation has 20"]);
+/// ```
+pub fn check_allocation<F>(lookup: F, topology: NodeTopology) -> Result<AllocationCheck, ParseError>
+where
+    F: Fn(&str) -> Option<String>,
+{
+    let mut check = if lookup("SLURM_JOB_ID").is_some() || lookup("SLURM_JOB_NUM_NODES").is_some() {
+        slurm_allocation(&lookup, topology)?
+    } else if lookup("PBS_JOBID").is_some() {
+        pbs_allocation(&lookup, topology)?
+    } else {
+        return Err(ParseError {
+            message: String::from("no Slurm or PBS job found in the environment"),
+        });
+    };
+
+    let geometry = check.geometry;
+    let res = Reservation::from_geometry(geometry);
+    // The reservation of a Slurm job always spans the allocation, so count
+    // the nodes its tasks need instead.
+    let needed_nodes = geometry.tasks.div_ceil(geometry.tasks_per_node());
+    if needed_nodes != check.nodes {
+        check.mismatches.push(format!(
+            "the job needs {} nodes but the allocation has {}",
+            needed_nodes, check.nodes
+        ));
+    }
+    if let Some(cpus) = check.cpus_on_node {
+        if cpus != geometry.usable_cpus() {
+            check.mismatches.push(format!(
+                "the allocation provides {} CPUs per node but {} were expected",
+                cpus,
+                geometry.usable_cpus()
+            ));
+        }
+        if res.max_node_tasks * geometry.threads > cpus {
+            check.mismatches.push(format!(
+                "{} PEs of {} threads on a node oversubscribe its {} CPUs",
+                res.max_node_tasks, geometry.threads, cpus
+            ));
+        }
+    }
+    Ok(check)
+}
+
+// The geometry of a Slurm job, checking that the number of OpenMP threads
+// matches the CPUs given to each task.
+fn slurm_allocation(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    topology: NodeTopology,
+) -> Result<AllocationCheck, ParseError> {
+    let nodes = required(lookup, "SLURM_JOB_NUM_NODES")?;
+    let mut slurm = SlurmOptions::default();
+    slurm.nodes = Some(nodes);
+    slurm.ntasks = number(lookup, "SLURM_NTASKS")?;
+    slurm.ntasks_per_node = number(lookup, "SLURM_NTASKS_PER_NODE")?;
+    slurm.cpus_per_task = number(lookup, "SLURM_CPUS_PER_TASK")?;
+    let geometry = slurm.geometry(topology)?;
+    let omp_threads = number(lookup, "OMP_NUM_THREADS")?;
+    let mut mismatches = Vec::new();
+    match omp_threads {
+        Some(threads) if threads != geometry.threads => mismatches.push(format!(
+            "OMP_NUM_THREADS is {} but each PE has {} CPUs (SLURM_CPUS_PER_TASK)",
+            threads, geometry.threads
+        )),
+        None if geometry.threads > 1 => mismatches.push(format!(
+            "OMP_NUM_THREADS is not set but each PE has {} CPUs (SLURM_CPUS_PER_TASK)",
+            geometry.threads
+        )),
+        _ => {}
+    }
+    Ok(AllocationCheck {
+        scheduler: "slurm",
+        geometry,
+        nodes,
+        cpus_on_node: number(lookup, "SLURM_CPUS_ON_NODE")?,
+        omp_threads,
+        mismatches,
+    })
+}
+
+// The geometry of a PBS job, PBS sets OMP_NUM_THREADS from the job's
+// ompthreads so this gives the threads per PE.
+fn pbs_allocation(
+    lookup: &dyn Fn(&str) -> Option<String>,
+    topology: NodeTopology,
+) -> Result<AllocationCheck, ParseError> {
+    let hosts = match lookup("PBS_NODEFILE") {
+        Some(path) => std::fs::read_to_string(&path).map_err(|e| ParseError {
+            message: format!("cannot read PBS_NODEFILE {}, {}", path, e),
+        })?,
+        None => String::new(),
+    };
+    let hosts: Vec<&str> = hosts
+        .lines()
+        .map(str::trim)
+        .filter(|h| !h.is_empty())
+        .collect();
+    let mut unique_hosts = hosts.clone();
+    unique_hosts.sort_unstable();
+    unique_hosts.dedup();
+    let tasks = match number(lookup, "PBS_NP")? {
+        Some(tasks) => tasks,
+        None if !hosts.is_empty() => hosts.len() as u32,
+        None => required(lookup, "PBS_NP")?,
+    };
+    let nodes = match number(lookup, "PBS_NUM_NODES")? {
+        Some(nodes) => nodes,
+        None => unique_hosts.len().max(1) as u32,
+    };
+    let omp_threads = number(lookup, "OMP_NUM_THREADS")?;
+    let geometry = Geometry::from_topology(topology, tasks, omp_threads.unwrap_or(1))?;
+    let tasks_per_node = unique_hosts
+        .iter()
+        .map(|host| hosts.iter().filter(|h| *h == host).count() as u32)
+        .max()
+        .unwrap_or_else(|| tasks.div_ceil(nodes));
+    let geometry = if tasks_per_node != geometry.tasks_per_node() {
+        geometry.with_tasks_per_node(tasks_per_node)?
+    } else {
+        geometry
+    };
+    let cpus_on_node = match number(lookup, "NCPUS")? {
+        Some(ncpus) => Some(ncpus),
+        None => number(lookup, "PBS_NUM_PPN")?,
+    };
+    Ok(AllocationCheck {
+        scheduler: "pbs",
+        geometry,
+        nodes,
+        cpus_on_node,
+        omp_threads,
+        mismatches: Vec::new(),
+    })
+}
+
+// The value of a numeric environment variable, if it is set.
+fn number(lookup: &dyn Fn(&str) -> Option<String>, name: &str) -> Result<Option<u32>, ParseError> {
+    match lookup(name) {
+        Some(value) => parse_count(name, value.trim()).map(Some),
+        None => Ok(None),
+    }
+}
+
+fn required(lookup: &dyn Fn(&str) -> Option<String>, name: &str) -> Result<u32, ParseError> {
+    number(lookup, name)?.ok_or_else(|| ParseError {
+        message: format!("{} is not set", name),
+    })
+}
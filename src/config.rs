@@ -1,95 +1,676 @@
-use serde::Deserialize;
+use serde::de::Deserializer;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use crate::searchopts::{self, CountSet, Locks, OptionValue, SearchOption, SEARCH_OPTIONS};
+use pestr::Distribution;
+
+static SYSTEM_CONFIG_FILE: &str = "/etc/pestr.toml";
+static USER_CONFIG_FILE: &str = "pestr/pestr.toml";
+static CONFIG_FILE_NAME: &str = ".pestr.toml";
 
 static DEFAULT_CPUS_PER_NODE: u32 = 128;
-static DEFAULT_SEARCH_CONSERVE_NODES: bool = false;
-static DEFAULT_SEARCH_PE_RADIUS: f32 = 0.25;
-static DEFAULT_SEARCH_THREAD_RADIUS: f32 = 0.5;
+static DEFAULT_SMT: u32 = 1;
+static DEFAULT_RESERVED_CORES: u32 = 0;
+static DEFAULT_DISTRIBUTION: Distribution = Distribution::Packed;
+static DEFAULT_SOCKETS: u32 = 1;
+static DEFAULT_DOMAINS_PER_SOCKET: u32 = 1;
+
+// A setting describing the nodes or how PEs are placed on them. Every setting
+// can be given in a configuration file, at the top level or for a machine, in
+// an environment variable, and with a command line option. The search options
+// are described separately by SEARCH_OPTIONS.
+pub struct ConfigSetting {
+    pub name: &'static str,
+    pub flag: &'static str,
+    pub env_name: &'static str,
+    // Parse the value of the environment variable into `Settings`.
+    parse: fn(&mut Settings, &str) -> Result<(), String>,
+    // Copy the setting into a configuration if it is given in `Settings`,
+    // returning whether it was.
+    set: fn(&mut Config, &Settings) -> bool,
+    pub get: fn(&Config) -> serde_json::Value,
+}
+
+pub static SETTINGS: &[ConfigSetting] = &[
+    ConfigSetting {
+        name: "cpus_per_node",
+        flag: "--cpus-per-node",
+        env_name: "PESTR_CPUS_PER_NODE",
+        parse: |settings, s| parse_into(&mut settings.cpus_per_node, s),
+        set: |config, settings| set_from(&mut config.cpus_per_node, settings.cpus_per_node),
+        get: |config| json!(config.cpus_per_node),
+    },
+    ConfigSetting {
+        name: "smt",
+        flag: "--smt",
+        env_name: "PESTR_SMT",
+        parse: |settings, s| parse_into(&mut settings.smt, s),
+        set: |config, settings| set_from(&mut config.smt, settings.smt),
+        get: |config| json!(config.smt),
+    },
+    ConfigSetting {
+        name: "reserved_cores",
+        flag: "--reserved-cores",
+        env_name: "PESTR_RESERVED_CORES",
+        parse: |settings, s| parse_into(&mut settings.reserved_cores, s),
+        set: |config, settings| set_from(&mut config.reserved_cores, settings.reserved_cores),
+        get: |config| json!(config.reserved_cores),
+    },
+    ConfigSetting {
+        name: "distribution",
+        flag: "--distribution",
+        env_name: "PESTR_DISTRIBUTION",
+        parse: |settings, s| parse_into(&mut settings.distribution, s),
+        set: |config, settings| set_from(&mut config.distribution, settings.distribution),
+        get: |config| json!(config.distribution),
+    },
+    ConfigSetting {
+        name: "sockets",
+        flag: "--sockets",
+        env_name: "PESTR_SOCKETS",
+        parse: |settings, s| parse_into(&mut settings.sockets, s),
+        set: |config, settings| set_from(&mut config.sockets, settings.sockets),
+        get: |config| json!(config.sockets),
+    },
+    ConfigSetting {
+        name: "domains_per_socket",
+        flag: "--domains-per-socket",
+        env_name: "PESTR_DOMAINS_PER_SOCKET",
+        parse: |settings, s| parse_into(&mut settings.domains_per_socket, s),
+        set: |config, settings| {
+            set_from(&mut config.domains_per_socket, settings.domains_per_socket)
+        },
+        get: |config| json!(config.domains_per_socket),
+    },
+];
+
+fn parse_into<T>(value: &mut Option<T>, s: &str) -> Result<(), String>
+where
+    T: FromStr,
+    T::Err: fmt::Display,
+{
+    *value = Some(
+        s.parse()
+            .map_err(|e| format!("cannot parse '{}', {}", s, e))?,
+    );
+    Ok(())
+}
+
+fn set_from<T>(value: &mut T, setting: Option<T>) -> bool {
+    match setting {
+        Some(setting) => {
+            *value = setting;
+            true
+        }
+        None => false,
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Error handling for configuration that cannot be read.
+pub struct ConfigError {
+    source: Option<String>,
+    location: Option<(usize, usize)>,
+    key: Option<String>,
+    message: String,
+}
+
+impl ConfigError {
+    fn new(message: String) -> Self {
+        Self {
+            source: None,
+            location: None,
+            key: None,
+            message,
+        }
+    }
+
+    fn in_file(path: &str, message: String) -> Self {
+        Self {
+            source: Some(path.to_owned()),
+            ..Self::new(message)
+        }
+    }
+
+    // An error in the TOML of a file, located using the error's span.
+    fn from_toml(path: &str, contents: &str, error: toml::de::Error) -> Self {
+        let offset = error.span().map(|span| span.start);
+        Self {
+            source: Some(path.to_owned()),
+            location: offset.map(|offset| line_and_column(contents, offset)),
+            key: offset.and_then(|offset| key_at(contents, offset)),
+            message: error.message().trim().replace('\n', ", "),
+        }
+    }
+
+    // An error in the value of `key` in a file, at a byte offset into it.
+    fn at_key(path: &str, contents: &str, offset: usize, key: String, message: String) -> Self {
+        Self {
+            source: Some(path.to_owned()),
+            location: Some(line_and_column(contents, offset)),
+            key: Some(key),
+            message,
+        }
+    }
+
+    fn from_env(name: &str, message: String) -> Self {
+        Self {
+            source: Some(format!("environment variable {}", name)),
+            ..Self::new(message)
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "invalid configuration")?;
+        if let Some(source) = &self.source {
+            write!(f, " in {}", source)?;
+        }
+        if let Some((line, column)) = self.location {
+            write!(f, " at line {}, column {}", line, column)?;
+        }
+        if let Some(key) = &self.key {
+            write!(f, ", key '{}'", key)?;
+        }
+        write!(f, ": {}", self.message)
+    }
+}
+
+impl fmt::Debug for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "ConfigError {{ source: {:?}, location: {:?}, key: {:?}, message: {} }}",
+            self.source, self.location, self.key, self.message
+        )
+    }
+}
+
+// Where the value of a setting came from.
+#[derive(Clone, Debug, Serialize)]
+#[serde(tag = "type", content = "name", rename_all = "lowercase")]
+pub enum Source {
+    Default,
+    File(String),
+    Env(String),
+    Cli(&'static str),
+}
+
+impl fmt::Display for Source {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Source::Default => write!(f, "default"),
+            Source::File(path) => write!(f, "{}", path),
+            Source::Env(name) => write!(f, "{}", name),
+            Source::Cli(flag) => write!(f, "{}", flag),
+        }
+    }
+}
 
 pub struct Config {
+    pub machine: Option<String>,
     pub cpus_per_node: u32,
+    pub smt: u32,
+    pub reserved_cores: u32,
+    pub distribution: Distribution,
+    pub sockets: u32,
+    pub domains_per_socket: u32,
     pub search: SearchConfig,
+    /// Problems with the configuration that do not stop it being used, such
+    /// as unknown keys in the file.
+    pub warnings: Vec<String>,
+    /// Where the value of each setting came from, keyed by the setting's name
+    /// in the file.
+    pub sources: BTreeMap<String, Source>,
 }
 
+#[derive(Clone, Default)]
 pub struct SearchConfig {
     pub conserve_nodes: bool,
     pub pe_radius: f32,
     pub thread_radius: f32,
+    pub pes: CountSet,
+    pub threads: CountSet,
+    pub nodes: CountSet,
+    pub lock: Locks,
+    pub max: Option<u32>,
 }
 
 impl Config {
-    pub fn new() -> Self {
-        Self::create(FileConfig::empty())
+    // Settings in later files take precedence over those in earlier files, and
+    // the settings given on the command line in `cli` take precedence over all.
+    pub fn from_files(
+        config_files: &[String],
+        machine: Option<&str>,
+        cli: &Settings,
+    ) -> Result<Self, ConfigError> {
+        let file_configs = config_files
+            .iter()
+            .map(|path| FileConfig::from_file(path))
+            .collect::<Result<Vec<FileConfig>, ConfigError>>()?;
+        Self::create(file_configs, machine, cli)
     }
 
-    pub fn from_file(config_file: &str) -> Self {
-        let file_config = FileConfig::from_file(config_file);
-        Self::create(file_config)
+    // The machine is chosen by the caller, or by the PESTR_MACHINE environment
+    // variable, or by the default machine of the last file that names one.
+    // Within each file settings for the machine take precedence over top-level
+    // settings, and then the files are merged in order of precedence, followed
+    // by the environment and the command line.
+    fn create(
+        file_configs: Vec<FileConfig>,
+        machine: Option<&str>,
+        cli: &Settings,
+    ) -> Result<Self, ConfigError> {
+        let warnings = file_configs
+            .iter()
+            .flat_map(|file_config| file_config.warnings.iter().cloned())
+            .collect();
+        let mut sources = BTreeMap::new();
+        let default_machine = file_configs.iter().rev().find_map(|file_config| {
+            Some((file_config.default_machine.clone()?, &file_config.path))
+        });
+        let machine_source = if machine.is_some() {
+            Source::Cli("--machine")
+        } else if read_from_env("PESTR_MACHINE").is_some() {
+            Source::Env(String::from("PESTR_MACHINE"))
+        } else {
+            match &default_machine {
+                Some((_, path)) => Source::File(path.to_string()),
+                None => Source::Default,
+            }
+        };
+        sources.insert(String::from("machine"), machine_source);
+        let machine = machine
+            .map(|m| m.to_owned())
+            .or_else(|| read_from_env("PESTR_MACHINE"))
+            .or(default_machine.map(|(name, _)| name));
+        if let Some(name) = &machine {
+            if !file_configs.iter().any(|c| c.machines.contains_key(name)) {
+                let message = format!("unknown machine '{}'", name);
+                let paths: Vec<&str> = file_configs.iter().map(|c| c.path.as_str()).collect();
+                return Err(if paths.is_empty() {
+                    ConfigError::new(message)
+                } else {
+                    ConfigError::in_file(&paths.join(", "), message)
+                });
+            }
+        }
+        let layers: Vec<(String, Settings)> = file_configs
+            .into_iter()
+            .map(|file_config| {
+                let settings = match machine
+                    .as_ref()
+                    .and_then(|name| file_config.machines.get(name))
+                {
+                    Some(machine_settings) => machine_settings.clone().or(file_config.settings),
+                    None => file_config.settings,
+                };
+                (file_config.path, settings)
+            })
+            .collect();
+
+        let mut env = Settings::default();
+        for setting in SETTINGS {
+            if let Some(value) = read_from_env(setting.env_name) {
+                (setting.parse)(&mut env, &value)
+                    .map_err(|e| ConfigError::from_env(setting.env_name, e))?;
+            }
+        }
+
+        let mut config = Self {
+            machine,
+            cpus_per_node: DEFAULT_CPUS_PER_NODE,
+            smt: DEFAULT_SMT,
+            reserved_cores: DEFAULT_RESERVED_CORES,
+            distribution: DEFAULT_DISTRIBUTION,
+            sockets: DEFAULT_SOCKETS,
+            domains_per_socket: DEFAULT_DOMAINS_PER_SOCKET,
+            search: SearchConfig::defaults(),
+            warnings,
+            sources,
+        };
+        for setting in SETTINGS {
+            let mut source = Source::Default;
+            for (path, settings) in &layers {
+                if (setting.set)(&mut config, settings) {
+                    source = Source::File(path.clone());
+                }
+            }
+            if (setting.set)(&mut config, &env) {
+                source = Source::Env(setting.env_name.to_owned());
+            }
+            if (setting.set)(&mut config, cli) {
+                source = Source::Cli(setting.flag);
+            }
+            config.sources.insert(setting.name.to_owned(), source);
+        }
+
+        for option in SEARCH_OPTIONS {
+            let env_name = option.env_name();
+            let env_value = read_from_env(&env_name)
+                .map(|s| {
+                    option
+                        .parse(Some(&s))
+                        .map_err(|e| ConfigError::from_env(&env_name, e))
+                })
+                .transpose()?;
+            let file_value = layers.iter().rev().find_map(|(path, settings)| {
+                Some((settings.search.values.get(option.name)?, path))
+            });
+            let source = match (env_value, file_value) {
+                (Some(value), _) => {
+                    option.set(&mut config.search, value);
+                    Source::Env(env_name)
+                }
+                (None, Some((value, path))) => {
+                    option.set(&mut config.search, value.clone());
+                    Source::File(path.clone())
+                }
+                (None, None) => Source::Default,
+            };
+            config.sources.insert(option.key(), source);
+        }
+
+        Ok(config)
     }
+}
 
-    fn create(file_config: FileConfig) -> Self {
-        let cpus_per_node = read_from_env("PESTR_CPUS_PER_NODE")
-            .map(|s| s.parse().unwrap())
-            .or(file_config.cpus_per_node)
-            .unwrap_or(DEFAULT_CPUS_PER_NODE);
+// A configuration file with every setting commented out at its default value.
+pub fn template() -> String {
+    format!(
+        r#"# pestr configuration file, uncomment and change the settings you need.
 
-        let conserve_nodes = read_from_env("PESTR_SEARCH_CONSERVE_NODES")
-            .map(|s| s.parse().unwrap())
-            .or(file_config.search.conserve_nodes)
-            .unwrap_or(DEFAULT_SEARCH_CONSERVE_NODES);
+# The number of physical CPUs per node.
+# cpus_per_node = {cpus_per_node}
 
-        let pe_radius = read_from_env("PESTR_SEARCH_PE_RADIUS")
-            .map(|s| s.parse().unwrap())
-            .or(file_config.search.pe_radius)
-            .unwrap_or(DEFAULT_SEARCH_PE_RADIUS);
+# The number of logical CPUs per physical CPU, e.g. 2 for hyperthreading.
+# smt = {smt}
 
-        let thread_radius = read_from_env("PESTR_SEARCH_THREAD_RADIUS")
-            .map(|s| s.parse().unwrap())
-            .or(file_config.search.thread_radius)
-            .unwrap_or(DEFAULT_SEARCH_THREAD_RADIUS);
+# The number of physical CPUs per node reserved for the system.
+# reserved_cores = {reserved_cores}
 
-        Self {
-            cpus_per_node,
-            search: SearchConfig {
-                conserve_nodes,
-                pe_radius,
-                thread_radius,
-            },
-        }
+# The distribution of PEs over nodes, one of "packed", "balanced" or "cyclic".
+# distribution = "{distribution}"
+
+# The layout of each node, the physical CPUs are split evenly between
+# sockets, and then between the NUMA domains within each socket.
+# sockets = {sockets}
+# domains_per_socket = {domains_per_socket}
+
+# The machine used when none is selected with --machine or PESTR_MACHINE.
+# default_machine = "mymachine"
+
+[search]
+{search}
+# Settings for a machine, any of the settings above may be given and take
+# precedence over the top-level settings when the machine is selected.
+# [machines.mymachine]
+# cpus_per_node = 128
+#
+# [machines.mymachine.search]
+# pe_radius = 0.1
+"#,
+        cpus_per_node = DEFAULT_CPUS_PER_NODE,
+        smt = DEFAULT_SMT,
+        reserved_cores = DEFAULT_RESERVED_CORES,
+        distribution = DEFAULT_DISTRIBUTION,
+        sockets = DEFAULT_SOCKETS,
+        domains_per_socket = DEFAULT_DOMAINS_PER_SOCKET,
+        search = SEARCH_OPTIONS
+            .iter()
+            .map(|option| format!(
+                "# {}{}.\n# {} = {}\n",
+                option.help[..1].to_uppercase(),
+                &option.help[1..],
+                option.name,
+                option.get(&SearchConfig::defaults()).to_toml()
+            ))
+            .collect::<Vec<String>>()
+            .join("\n"),
+    )
+}
+
+// The names of the machines defined in any of the configuration files.
+pub fn machine_names(config_files: &[String]) -> Result<Vec<String>, ConfigError> {
+    let mut names = BTreeSet::new();
+    for path in config_files {
+        names.extend(FileConfig::from_file(path)?.machines.into_keys());
     }
+    Ok(names.into_iter().collect())
 }
 
-#[derive(Deserialize)]
+// The configuration files that exist, in increasing order of precedence: the
+// system file, the user's files, and the nearest project file found in the
+// current directory or one of its parents.
+pub fn config_files() -> Vec<String> {
+    let home_file = dirs::home_dir().map(|home| home.join(CONFIG_FILE_NAME));
+    let project_file = std::env::current_dir().ok().and_then(|cwd| {
+        cwd.ancestors()
+            .map(|dir| dir.join(CONFIG_FILE_NAME))
+            .find(|path| path.is_file() && Some(path) != home_file.as_ref())
+    });
+    let candidates = [
+        Some(PathBuf::from(SYSTEM_CONFIG_FILE)),
+        dirs::config_dir().map(|dir| dir.join(USER_CONFIG_FILE)),
+        home_file.clone(),
+        project_file,
+    ];
+    candidates
+        .iter()
+        .flatten()
+        .filter(|path| path.is_file())
+        .filter_map(|path| path_string(path))
+        .collect()
+}
+
+// The configuration file in the user's home directory, whether or not it exists.
+pub fn home_config_file() -> Option<String> {
+    dirs::home_dir().and_then(|home| path_string(&home.join(CONFIG_FILE_NAME)))
+}
+
+fn path_string(path: &Path) -> Option<String> {
+    path.to_str().map(|s| s.to_owned())
+}
+
+#[derive(Default)]
 struct FileConfig {
-    cpus_per_node: Option<u32>,
-    search: FileSearchConfig,
+    default_machine: Option<String>,
+    settings: Settings,
+    machines: BTreeMap<String, Settings>,
+    warnings: Vec<String>,
+    path: String,
 }
 
+// The parts of a configuration file other than its top-level settings. These
+// are read separately from the settings, as flattening them into one struct
+// loses the location of errors in the file.
 #[derive(Deserialize)]
+struct FileMachines {
+    default_machine: Option<String>,
+    #[serde(default)]
+    machines: BTreeMap<String, Settings>,
+}
+
+// Settings that may be given at the top level of the file or for a machine,
+// and that may be given in the environment or on the command line.
+#[derive(Deserialize, Default, Clone)]
+pub struct Settings {
+    pub cpus_per_node: Option<u32>,
+    pub smt: Option<u32>,
+    pub reserved_cores: Option<u32>,
+    pub distribution: Option<Distribution>,
+    pub sockets: Option<u32>,
+    pub domains_per_socket: Option<u32>,
+    #[serde(default)]
+    search: FileSearchConfig,
+}
+
+// The search options set in a search section, keyed by name.
+#[derive(Default, Clone)]
 struct FileSearchConfig {
-    conserve_nodes: Option<bool>,
-    pe_radius: Option<f32>,
-    thread_radius: Option<f32>,
+    values: BTreeMap<&'static str, OptionValue>,
+    // The options as written in the file, which `resolve` converts into
+    // `values` once the position of an invalid value can be reported.
+    unresolved: Vec<(&'static SearchOption, toml::Spanned<toml::Value>)>,
+}
+
+impl<'de> Deserialize<'de> for FileSearchConfig {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let table = BTreeMap::<String, toml::Spanned<toml::Value>>::deserialize(deserializer)?;
+        let unresolved = table
+            .into_iter()
+            // Unknown keys are reported as warnings by `unknown_keys`.
+            .filter_map(|(key, value)| Some((searchopts::find(&key)?, value)))
+            .collect();
+        Ok(Self {
+            values: BTreeMap::new(),
+            unresolved,
+        })
+    }
+}
+
+impl FileSearchConfig {
+    // Convert the options read from the table named `table` in a file.
+    fn resolve(&mut self, path: &str, contents: &str, table: &str) -> Result<(), ConfigError> {
+        for (option, value) in std::mem::take(&mut self.unresolved) {
+            let parsed = option.parse_toml(value.get_ref()).map_err(|message| {
+                let key = format!("{}.{}", table, option.name);
+                ConfigError::at_key(path, contents, value.span().start, key, message)
+            })?;
+            self.values.insert(option.name, parsed);
+        }
+        Ok(())
+    }
 }
 
 impl FileConfig {
-    pub fn from_file(path: &str) -> Self {
-        match std::fs::read_to_string(path) {
-            Ok(file_contents) => {
-                let config: Self =
-                    toml::from_str(&file_contents).unwrap_or_else(|_| FileConfig::empty());
-                config
+    pub fn from_file(path: &str) -> Result<Self, ConfigError> {
+        let contents = std::fs::read_to_string(path)
+            .map_err(|e| ConfigError::in_file(path, format!("cannot read file, {}", e)))?;
+        let table: toml::Table =
+            toml::from_str(&contents).map_err(|e| ConfigError::from_toml(path, &contents, e))?;
+        let mut settings: Settings =
+            toml::from_str(&contents).map_err(|e| ConfigError::from_toml(path, &contents, e))?;
+        let FileMachines {
+            default_machine,
+            mut machines,
+        } = toml::from_str(&contents).map_err(|e| ConfigError::from_toml(path, &contents, e))?;
+        settings.search.resolve(path, &contents, "search")?;
+        for (name, machine) in &mut machines {
+            let table = format!("machines.{}.search", name);
+            machine.search.resolve(path, &contents, &table)?;
+        }
+        Ok(Self {
+            default_machine,
+            settings,
+            machines,
+            warnings: unknown_keys(&table)
+                .into_iter()
+                .map(|key| format!("unknown key '{}' in {}", key, path))
+                .collect(),
+            path: path.to_owned(),
+        })
+    }
+}
+
+// The keys in a configuration file that pestr does not understand.
+fn unknown_keys(table: &toml::Table) -> Vec<String> {
+    let mut unknown = Vec::new();
+    settings_unknown_keys("", table, &["default_machine", "machines"], &mut unknown);
+    if let Some(toml::Value::Table(machines)) = table.get("machines") {
+        for (name, machine) in machines {
+            if let toml::Value::Table(settings) = machine {
+                let prefix = format!("machines.{}.", name);
+                settings_unknown_keys(&prefix, settings, &[], &mut unknown);
             }
-            Err(_) => FileConfig::empty(),
         }
     }
+    unknown
+}
+
+// Add the keys in a table of settings that are neither settings nor one of
+// `extra_keys` to `unknown`, with their names prefixed by `prefix`.
+fn settings_unknown_keys(
+    prefix: &str,
+    settings: &toml::Table,
+    extra_keys: &[&str],
+    unknown: &mut Vec<String>,
+) {
+    for (key, value) in settings {
+        if extra_keys.contains(&key.as_str()) {
+            continue;
+        }
+        if key != "search" && !SETTINGS.iter().any(|setting| setting.name == key) {
+            unknown.push(format!("{}{}", prefix, key));
+        } else if let ("search", toml::Value::Table(search)) = (key.as_str(), value) {
+            for key in search.keys() {
+                if searchopts::find(key).is_none() {
+                    unknown.push(format!("{}search.{}", prefix, key));
+                }
+            }
+        }
+    }
+}
+
+// The 1-based line and column of a byte offset into a file.
+fn line_and_column(contents: &str, offset: usize) -> (usize, usize) {
+    let before = &contents[..offset.min(contents.len())];
+    let line = before.matches('\n').count() + 1;
+    let column = before.chars().rev().take_while(|&c| c != '\n').count() + 1;
+    (line, column)
+}
+
+// The full name of the key assigned on the line containing a byte offset into
+// a file, including the names of the tables it is in.
+fn key_at(contents: &str, offset: usize) -> Option<String> {
+    let (line_number, _) = line_and_column(contents, offset);
+    let mut table = String::new();
+    for line in contents.lines().take(line_number) {
+        let line = line.trim();
+        if line.starts_with('[') {
+            table = line
+                .trim_matches(|c| c == '[' || c == ']')
+                .trim()
+                .to_owned();
+        }
+    }
+    let line = contents.lines().nth(line_number - 1)?;
+    let (key, _) = line.split_once('=')?;
+    let key = key.trim().trim_matches('"');
+    if key.is_empty() || key.starts_with('#') || key.starts_with('[') {
+        return None;
+    }
+    Some(if table.is_empty() {
+        key.to_owned()
+    } else {
+        format!("{}.{}", table, key)
+    })
+}
 
-    fn empty() -> Self {
+impl Settings {
+    // These settings, with any that are not set taken from `other`.
+    fn or(self, other: Settings) -> Self {
         Self {
-            cpus_per_node: None,
+            cpus_per_node: self.cpus_per_node.or(other.cpus_per_node),
+            smt: self.smt.or(other.smt),
+            reserved_cores: self.reserved_cores.or(other.reserved_cores),
+            distribution: self.distribution.or(other.distribution),
+            sockets: self.sockets.or(other.sockets),
+            domains_per_socket: self.domains_per_socket.or(other.domains_per_socket),
             search: FileSearchConfig {
-                conserve_nodes: None,
-                pe_radius: None,
-                thread_radius: None,
+                values: other
+                    .search
+                    .values
+                    .into_iter()
+                    .chain(self.search.values)
+                    .collect(),
+                unresolved: Vec::new(),
             },
         }
     }
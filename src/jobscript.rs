@@ -0,0 +1,139 @@
+//! Extraction of job geometries from Slurm and PBS batch scripts.
+use serde::Serialize;
+
+use crate::launcher::{launcher_geometry, split_words, SlurmOptions};
+use crate::{pbs, Geometry, NodeTopology, ParseError};
+
+/// A job geometry found in a batch script.
+#[derive(Clone, Copy, Debug, Serialize)]
+pub struct ScriptJob {
+    /// The line of the script the geometry was found on, counting from 1.
+    pub line: usize,
+    /// What the geometry was read from: `srun`, `aprun`, `mpiexec` or
+    /// `mpirun` for a launcher command, or `sbatch` or `pbs` for batch
+    /// directives.
+    pub source: &'static str,
+    /// The geometry of the job.
+    pub geometry: Geometry,
+}
+
+/// Find the job geometries in a Slurm or PBS batch script.
+///
+/// Every `srun`, `aprun`, `mpiexec` and `mpirun` command in the script yields
+/// a geometry, with `srun` inheriting any options not given on its command
+/// line from the script's `#SBATCH` directives. If there are no launcher commands then a
+/// single geometry is read from the `#SBATCH` directives, or from a
+/// `#PBS -l select=...` directive.
+///
+/// Scripts don't describe the nodes they run on, so the physical layout of
+/// the nodes is taken from `topology`. Its SMT level is used unless the script
+/// selects a different one, for example with `--hint=nomultithread`.
+///
+/// # Arguments
+///
+/// * `script` - The text of the batch script.
+/// * `topology` - The layout of the CPUs within each node.
+///
+/// # Examples
+/// ```
+/// use pestr::{jobscript, NodeTopology, Reservation};
+/// let script = "#!/bin/bash
+/// #SBATCH --nodes=13
+/// #SBATCH --ntasks=128
+/// #SBATCH --cpus-per-task=12
+/// srun ./model
+/// ";
+/// let topology = NodeTopology::new(1, 1, 128, 1).unwrap();
+/// let jobs = jobscript::parse_script(script, topology).unwrap();
+/// assert_eq!(jobs.len(), 1);
+/// assert_eq!((jobs[0].line, jobs[0].source), (5, "srun"));
+/// assert_eq!(Reservation::from_geometry(jobs[0].geometry).idle_cpus, 128);
+/// ```
+///
+/// Nodes requested beyond those the tasks need are counted as idle:
+/// ```
+/// use pestr::{jobscript, NodeTopology, Reservation};
+/// let script = "#SBATCH --nodes=20 --ntasks=128 --cpus-per-task=12\n";
+/// let topology = NodeTopology::new(1, 1, 128, 1).unwrap();
+/// let jobs = jobscript::parse_script(script, topology).unwrap();
+/// let res = Reservation::from_geometry(jobs[0].geometry);
+/// assert_eq!((res.nodes, res.cpus, res.idle_cpus), (20, 2560, 1024));
+/// ```
+pub fn parse_script(script: &str, topology: NodeTopology) -> Result<Vec<ScriptJob>, ParseError> {
+    let mut directives = SlurmOptions::default();
+    let mut directive_line = None;
+    let mut select = None;
+    let mut jobs = Vec::new();
+    for (line, text) in logical_lines(script) {
+        let text = text.trim();
+        let with_line = |e: ParseError| ParseError {
+            message: format!("line {}: {}", line, e.message),
+        };
+        if let Some(options) = text.strip_prefix("#SBATCH") {
+            let words = split_words(options);
+            directives.parse(&words).map_err(with_line)?;
+            directive_line.get_or_insert(line);
+        } else if let Some(options) = text.strip_prefix("#PBS") {
+            let words = split_words(options);
+            let resources = words.windows(2).filter(|w| w[0] == "-l").map(|w| &w[1]);
+            for resource in resources.flat_map(|r| r.split(',')) {
+                if resource.starts_with("select=") {
+                    let geometry = pbs::parse_select(resource, topology.smt).map_err(with_line)?;
+                    select = Some((line, geometry));
+                }
+            }
+        } else if !text.starts_with('#') {
+            let words = split_words(text);
+            if let Some(parsed) = launcher_geometry(&words, topology, &directives) {
+                let (source, geometry) = parsed.map_err(with_line)?;
+                jobs.push(ScriptJob {
+                    line,
+                    source,
+                    geometry,
+                });
+            }
+        }
+    }
+    if jobs.is_empty() {
+        if let Some(line) = directive_line {
+            let geometry = directives.geometry(topology).map_err(|e| ParseError {
+                message: format!("line {}: {}", line, e.message),
+            })?;
+            jobs.push(ScriptJob {
+                line,
+                source: "sbatch",
+                geometry,
+            });
+        } else if let Some((line, geometry)) = select {
+            jobs.push(ScriptJob {
+                line,
+                source: "pbs",
+                geometry,
+            });
+        }
+    }
+    Ok(jobs)
+}
+
+// The lines of a script, with lines ending in a backslash joined to the next
+// line, paired with the line number they start on.
+fn logical_lines(script: &str) -> Vec<(usize, String)> {
+    let mut lines = Vec::new();
+    let mut current: Option<(usize, String)> = None;
+    for (i, line) in script.lines().enumerate() {
+        let (start, mut text) = current.take().unwrap_or((i + 1, String::new()));
+        match line.strip_suffix('\\') {
+            Some(continued) => {
+                text.push_str(continued);
+                text.push(' ');
+                current = Some((start, text));
+            }
+            None => {
+                text.push_str(line);
+                lines.push((start, text));
+            }
+        }
+    }
+    lines.extend(current);
+    lines
+}
@@ -0,0 +1,500 @@
+//! Extraction of job geometries from launcher command lines.
+use crate::{Distribution, Geometry, NodeTopology, ParseError};
+
+/// Parse a launcher command line into the geometry of the job it starts.
+///
+/// The command may be an `srun`, `aprun`, `mpiexec` or `mpirun` command, and
+/// may be preceded by environment variable assignments such as
+/// `OMP_NUM_THREADS=16`. Options that do not affect the geometry are ignored,
+/// as is everything after the program the launcher runs.
+///
+/// Commands don't describe the nodes they run on, so the physical layout of
+/// the nodes is taken from `topology`. Its SMT level is used unless the
+/// command selects a different one, for example with `--hint=nomultithread`.
+///
+/// # Arguments
+///
+/// * `command` - The launcher command line.
+/// * `topology` - The layout of the CPUs within each node.
+///
+/// # Examples
+/// ```
+/// use pestr::{launcher, NodeTopology, Reservation};
+/// let topology = NodeTopology::new(2, 4, 16, 2).unwrap();
+/// let geom = launcher::parse_command("srun -n 512 -c 16 --hint=nomultithread ./model", topology)
+///     .unwrap();
+/// assert_eq!((geom.tasks, geom.threads, geom.smt()), (512, 16, 1));
+/// assert_eq!(Reservation::from_geometry(geom).nodes, 64);
+///
+/// let geom = launcher::parse_command("aprun -n 256 -N 16 -d 16 -j 2 ./model", topology)
+///     .unwrap();
+/// assert_eq!(Reservation::from_geometry(geom).nodes, 16);
+///
+/// let geom = launcher::parse_command("mpirun -np 64 --map-by ppr:4:socket:PE=8 ./model", topology)
+///     .unwrap();
+/// assert_eq!((geom.tasks_per_node(), Reservation::from_geometry(geom).nodes), (8, 8));
+///
+/// let command = "srun -N 100000 --ntasks-per-node=100000 ./model";
+/// assert!(launcher::parse_command(command, topology).is_err());
+/// ```
+pub fn parse_command(command: &str, topology: NodeTopology) -> Result<Geometry, ParseError> {
+    let words = split_words(&command.replace("\\\n", " "));
+    match launcher_geometry(&words, topology, &SlurmOptions::default()) {
+        Some(parsed) => parsed.map(|(_, geom)| geom),
+        None => Err(ParseError {
+            message: String::from("no srun, aprun, mpiexec or mpirun command found"),
+        }),
+    }
+}
+
+// The name of the launcher in a command and the geometry it describes, or
+// None if the words are not a launcher command. Any options not given to srun
+// are taken from `directives`.
+pub(crate) fn launcher_geometry(
+    words: &[String],
+    topology: NodeTopology,
+    directives: &SlurmOptions,
+) -> Option<Result<(&'static str, Geometry), ParseError>> {
+    let command = words
+        .iter()
+        .position(|w| !w.contains('=') && w != "exec" && w != "time" && w != "env")?;
+    let environment: Vec<(String, String)> = words[..command]
+        .iter()
+        .filter_map(|w| w.split_once('='))
+        .map(|(key, value)| (key.to_string(), value.to_string()))
+        .collect();
+    let options = &words[command + 1..];
+    let name = words[command].rsplit('/').next().unwrap();
+    match name.split('.').next().unwrap() {
+        "srun" => {
+            let mut srun = directives.clone();
+            Some(
+                srun.parse(options)
+                    .and_then(|_| srun.geometry(topology))
+                    .map(|g| ("srun", g)),
+            )
+        }
+        "aprun" => Some(aprun_geometry(options, topology).map(|g| ("aprun", g))),
+        "mpiexec" => Some(mpi_geometry(options, &environment, topology).map(|g| ("mpiexec", g))),
+        "mpirun" => Some(mpi_geometry(options, &environment, topology).map(|g| ("mpirun", g))),
+        _ => None,
+    }
+}
+
+// The options that determine the geometry of a Slurm job or job step.
+#[derive(Clone, Default)]
+pub(crate) struct SlurmOptions {
+    pub(crate) nodes: Option<u32>,
+    pub(crate) ntasks: Option<u32>,
+    pub(crate) ntasks_per_node: Option<u32>,
+    pub(crate) cpus_per_task: Option<u32>,
+    threads_per_core: Option<u32>,
+    multithread: Option<bool>,
+    distribution: Option<Distribution>,
+    core_spec: Option<u32>,
+}
+
+// Slurm options that take a value but do not affect the geometry.
+static SLURM_VALUE_OPTIONS: &[&str] = &[
+    "-A",
+    "--account",
+    "-p",
+    "--partition",
+    "-t",
+    "--time",
+    "-J",
+    "--job-name",
+    "-o",
+    "--output",
+    "-e",
+    "--error",
+    "-q",
+    "--qos",
+    "-C",
+    "--constraint",
+    "-w",
+    "--nodelist",
+    "-x",
+    "--exclude",
+    "--mem",
+    "--mem-per-cpu",
+    "--mpi",
+    "--cpu-bind",
+    "--export",
+    "--gres",
+    "--gpus",
+    "-D",
+    "--chdir",
+    "--mail-type",
+    "--mail-user",
+    "--reservation",
+    "-i",
+    "--input",
+];
+
+impl SlurmOptions {
+    pub(crate) fn parse(&mut self, words: &[String]) -> Result<(), ParseError> {
+        let mut words = words.iter();
+        while let Some(word) = words.next() {
+            if !word.starts_with('-') {
+                break;
+            }
+            let (name, attached) = split_option(word);
+            let value = || -> Result<String, ParseError> {
+                attached.map(Ok).unwrap_or_else(|| {
+                    words.next().cloned().ok_or_else(|| ParseError {
+                        message: format!("option {} needs a value", name),
+                    })
+                })
+            };
+            match name {
+                "-N" | "--nodes" => self.nodes = Some(parse_count(name, &value()?)?),
+                "-n" | "--ntasks" => self.ntasks = Some(parse_count(name, &value()?)?),
+                "--ntasks-per-node" => self.ntasks_per_node = Some(parse_count(name, &value()?)?),
+                "-c" | "--cpus-per-task" => {
+                    self.cpus_per_task = Some(parse_count(name, &value()?)?)
+                }
+                "--threads-per-core" => self.threads_per_core = Some(parse_count(name, &value()?)?),
+                "-S" | "--core-spec" => self.core_spec = Some(parse_number(name, &value()?)?),
+                "--hint" => match value()?.as_str() {
+                    "nomultithread" => self.multithread = Some(false),
+                    "multithread" => self.multithread = Some(true),
+                    _ => {}
+                },
+                "-m" | "--distribution" => {
+                    // Block distribution places as many tasks on a node as
+                    // the other options allow, so only cyclic changes how
+                    // tasks are laid out.
+                    self.distribution = match value()?.split(':').next() {
+                        Some("cyclic") => Some(Distribution::Cyclic),
+                        _ => None,
+                    }
+                }
+                _ => {
+                    if SLURM_VALUE_OPTIONS.contains(&name) {
+                        value()?;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn geometry(&self, topology: NodeTopology) -> Result<Geometry, ParseError> {
+        let smt = match (self.threads_per_core, self.multithread) {
+            (Some(threads_per_core), _) => threads_per_core,
+            (None, Some(false)) => 1,
+            (None, _) => topology.smt,
+        };
+        let topology = NodeTopology { smt, ..topology };
+        let tasks = match (self.ntasks, self.nodes, self.ntasks_per_node) {
+            (Some(ntasks), _, _) => ntasks,
+            (None, Some(nodes), Some(ntasks_per_node)) => nodes
+                .checked_mul(ntasks_per_node)
+                .ok_or_else(|| ParseError {
+                    message: format!(
+                        "{} nodes of {} tasks are too many tasks to count",
+                        nodes, ntasks_per_node
+                    ),
+                })?,
+            (None, Some(nodes), None) => nodes,
+            (None, None, _) => 1,
+        };
+        let threads = self.cpus_per_task.unwrap_or(1);
+        let mut geom = Geometry::from_topology(topology, tasks, threads)?
+            .with_reserved_cores(self.core_spec.unwrap_or(0))?;
+        if let Some(ntasks_per_node) = self.ntasks_per_node {
+            if ntasks_per_node != geom.tasks_per_node() {
+                geom = geom.with_tasks_per_node(ntasks_per_node)?;
+            }
+        }
+        // When a node count is given without a task count per node Slurm
+        // spreads the tasks over all of the nodes, any nodes beyond those
+        // needed are still reserved.
+        let distribution = match (self.ntasks_per_node, self.nodes) {
+            (None, Some(_)) => Distribution::Balanced,
+            _ => Distribution::Packed,
+        };
+        if let Some(nodes) = self.nodes {
+            geom = geom.with_nodes(nodes)?;
+        }
+        Ok(geom.with_distribution(self.distribution.unwrap_or(distribution)))
+    }
+}
+
+// aprun options that take a value but do not affect the geometry.
+static APRUN_VALUE_OPTIONS: &[&str] = &[
+    "-S", "-cc", "-L", "-m", "-e", "-a", "-p", "-sl", "-sn", "-ss", "-t", "-F", "-D", "-E",
+];
+
+// The geometry described by the options of an aprun command.
+fn aprun_geometry(words: &[String], topology: NodeTopology) -> Result<Geometry, ParseError> {
+    let mut tasks = 1;
+    let mut tasks_per_node = None;
+    let mut depth = 1;
+    let mut smt = topology.smt;
+    let mut reserved_cores = 0;
+    let mut words = words.iter();
+    while let Some(word) = words.next() {
+        if !word.starts_with('-') {
+            break;
+        }
+        let (name, attached) = split_option(word);
+        let value = || -> Result<String, ParseError> {
+            attached.map(Ok).unwrap_or_else(|| {
+                words.next().cloned().ok_or_else(|| ParseError {
+                    message: format!("option {} needs a value", name),
+                })
+            })
+        };
+        match name {
+            "-n" => tasks = parse_count(name, &value()?)?,
+            "-N" => tasks_per_node = Some(parse_count(name, &value()?)?),
+            "-d" => depth = parse_count(name, &value()?)?,
+            "-j" => match parse_number(name, &value()?)? {
+                0 => {}
+                cpus_per_core => smt = cpus_per_core,
+            },
+            "-r" => reserved_cores = parse_number(name, &value()?)?,
+            _ => {
+                if APRUN_VALUE_OPTIONS.contains(&name) {
+                    value()?;
+                }
+            }
+        }
+    }
+    let topology = NodeTopology { smt, ..topology };
+    let geom =
+        Geometry::from_topology(topology, tasks, depth)?.with_reserved_cores(reserved_cores)?;
+    match tasks_per_node {
+        Some(n) if n != geom.tasks_per_node() => Ok(geom.with_tasks_per_node(n)?),
+        _ => Ok(geom),
+    }
+}
+
+// MPI launcher options that take a value but do not affect the geometry.
+static MPI_VALUE_OPTIONS: &[&str] = &[
+    "-f",
+    "-hostfile",
+    "--hostfile",
+    "-machinefile",
+    "--machinefile",
+    "-H",
+    "-host",
+    "--host",
+    "-hosts",
+    "--hosts",
+    "--bind-to",
+    "--cpu-bind",
+    "-binding",
+    "--rank-by",
+    "--rankfile",
+    "--prefix",
+    "-wdir",
+    "--wdir",
+    "-configfile",
+    "-genvlist",
+    "--envlist",
+];
+
+// The geometry described by the options of an mpiexec or mpirun command, these
+// cover HPE PALS, Open MPI, Intel MPI and MPICH. The number of threads is taken
+// from the launcher's own options if it has them, otherwise from any setting
+// of OMP_NUM_THREADS or I_MPI_PIN_DOMAIN on the command line.
+fn mpi_geometry(
+    words: &[String],
+    environment: &[(String, String)],
+    topology: NodeTopology,
+) -> Result<Geometry, ParseError> {
+    let mut tasks = 1;
+    let mut tasks_per_node = None;
+    let mut depth = None;
+    let mut cpus_per_rank = None;
+    let mut hwthreads = false;
+    let mut distribution = Distribution::Packed;
+    let mut environment = environment.to_vec();
+    let mut words = words.iter();
+    while let Some(word) = words.next() {
+        if !word.starts_with('-') {
+            break;
+        }
+        let (name, attached) = split_option(word);
+        let mut value = || -> Result<String, ParseError> {
+            attached.clone().map(Ok).unwrap_or_else(|| {
+                words.next().cloned().ok_or_else(|| ParseError {
+                    message: format!("option {} needs a value", name),
+                })
+            })
+        };
+        match name {
+            "-n" | "-np" | "--n" | "--np" => tasks = parse_count(name, &value()?)?,
+            "-N" | "-ppn" | "--ppn" | "-perhost" | "-npernode" | "--npernode" => {
+                tasks_per_node = Some(parse_count(name, &value()?)?)
+            }
+            "-d" | "--depth" => depth = Some(parse_count(name, &value()?)?),
+            "--map-by" => {
+                let map_by = value()?;
+                let mut parts = map_by.split(':');
+                match parts.next() {
+                    Some("ppr") => {
+                        let count = parse_count(name, parts.next().unwrap_or(""))?;
+                        // The count is per object, so scale it to a whole node.
+                        let objects = match parts.next() {
+                            Some("node") => 1,
+                            Some("socket") | Some("package") => topology.sockets,
+                            Some("numa") | Some("l3cache") => topology.domains(),
+                            Some(object) => {
+                                return Err(ParseError {
+                                    message: format!(
+                                        "unsupported object '{}' in {} {}",
+                                        object, name, map_by
+                                    ),
+                                })
+                            }
+                            None => {
+                                return Err(ParseError {
+                                    message: format!("{} {} needs an object", name, map_by),
+                                })
+                            }
+                        };
+                        let count = count.checked_mul(objects).ok_or_else(|| ParseError {
+                            message: format!("too many tasks per node in {} {}", name, map_by),
+                        })?;
+                        tasks_per_node = Some(count)
+                    }
+                    Some("node") => distribution = Distribution::Cyclic,
+                    _ => {}
+                }
+                for part in parts {
+                    if let Some(pe) = part.strip_prefix("PE=").or(part.strip_prefix("pe=")) {
+                        cpus_per_rank = Some(parse_count(name, pe)?);
+                    }
+                }
+            }
+            "--rank-by" => {
+                if value()?.starts_with("node") {
+                    distribution = Distribution::Cyclic;
+                }
+            }
+            "--use-hwthread-cpus" => hwthreads = true,
+            "-x" | "--env" => {
+                if let Some((key, val)) = value()?.split_once('=') {
+                    environment.push((key.to_string(), val.to_string()));
+                }
+            }
+            "-genv" | "-env" => {
+                let key = value()?;
+                environment.push((key, value()?));
+            }
+            "--mca" | "-mca" => {
+                value()?;
+                value()?;
+            }
+            _ => {
+                if MPI_VALUE_OPTIONS.contains(&name) {
+                    value()?;
+                }
+            }
+        }
+    }
+    let variable = |key: &str| {
+        environment
+            .iter()
+            .rev()
+            .find(|(k, _)| k == key)
+            .map(|(_, v)| v.as_str())
+    };
+    let omp_threads = match variable("OMP_NUM_THREADS") {
+        Some(threads) => Some(parse_count("OMP_NUM_THREADS", threads)?),
+        None => None,
+    };
+    // Intel MPI pinning domains are given as a size and an optional layout,
+    // e.g. 16:compact, or as a named domain such as omp.
+    let pin_domain = variable("I_MPI_PIN_DOMAIN")
+        .and_then(|domain| domain.split(':').next())
+        .and_then(|size| size.parse().ok());
+    // Open MPI counts processing elements in cores unless told to use
+    // hardware threads.
+    let smt = match cpus_per_rank {
+        Some(_) if !hwthreads => 1,
+        _ => topology.smt,
+    };
+    let threads = depth
+        .or(cpus_per_rank)
+        .or(pin_domain)
+        .or(omp_threads)
+        .unwrap_or(1);
+    let topology = NodeTopology { smt, ..topology };
+    let geom = Geometry::from_topology(topology, tasks, threads)?.with_distribution(distribution);
+    match tasks_per_node {
+        Some(n) if n != geom.tasks_per_node() => Ok(geom.with_tasks_per_node(n)?),
+        _ => Ok(geom),
+    }
+}
+
+// Split an option into its name and value, if the value is attached to it
+// as in `--ntasks=4` or `-n4`.
+fn split_option(word: &str) -> (&str, Option<String>) {
+    if word.starts_with("--") {
+        match word.split_once('=') {
+            Some((name, value)) => (name, Some(value.to_string())),
+            None => (word, None),
+        }
+    } else if word.len() > 2 && word[2..].chars().all(|c| c.is_ascii_digit()) {
+        (&word[..2], Some(word[2..].to_string()))
+    } else {
+        (word, None)
+    }
+}
+
+// Parse a count that must be at least 1, for a range such as `--nodes=2-4`
+// the lower bound is used.
+pub(crate) fn parse_count(name: &str, value: &str) -> Result<u32, ParseError> {
+    match parse_number(name, value)? {
+        0 => Err(ParseError {
+            message: format!("option {} must be > 0", name),
+        }),
+        count => Ok(count),
+    }
+}
+
+fn parse_number(name: &str, value: &str) -> Result<u32, ParseError> {
+    let lower = value.split('-').next().unwrap_or(value);
+    lower.parse().map_err(|_| ParseError {
+        message: format!("invalid value '{}' for option {}", value, name),
+    })
+}
+
+// Split a line into shell words, removing quotes and stopping at a comment.
+pub(crate) fn split_words(line: &str) -> Vec<String> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut in_word = false;
+    let mut quote = None;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some(_), c) => word.push(c),
+            (None, '\'') | (None, '"') => {
+                quote = Some(c);
+                in_word = true;
+            }
+            (None, '#') if !in_word => break,
+            (None, ';') | (None, '|') | (None, '&') => break,
+            (None, c) if c.is_whitespace() => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                word.push(c);
+                in_word = true;
+            }
+        }
+    }
+    if in_word {
+        words.push(word);
+    }
+    words
+}
@@ -1,5 +1,11 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use std::fmt;
+use std::str::FromStr;
+
+pub mod allocation;
+pub mod jobscript;
+pub mod launcher;
+pub mod pbs;
 
 // ---------------------------------------------------------------------------
 // Error handling for bad geometry sizes.
@@ -19,13 +25,211 @@ impl fmt::Debug for GeometryError {
     }
 }
 
+// ---------------------------------------------------------------------------
+// Error handling for job descriptions that cannot be parsed.
+pub struct ParseError {
+    message: String,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "cannot parse job, {}", self.message)
+    }
+}
+
+impl fmt::Debug for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "ParseError {{ message: {} }}", self.message)
+    }
+}
+
+impl From<GeometryError> for ParseError {
+    fn from(e: GeometryError) -> Self {
+        ParseError { message: e.message }
+    }
+}
+
+/// The layout of the CPUs within a single compute node.
+///
+/// A node is made up of one or more sockets, each socket is divided into one
+/// or more NUMA domains, and each NUMA domain contains a number of physical
+/// cores. Each physical core may present more than one logical CPU when
+/// simultaneous multithreading (SMT) is in use.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct NodeTopology {
+    /// The number of CPU sockets in a node.
+    pub sockets: u32,
+    /// The number of NUMA domains in each socket.
+    pub domains_per_socket: u32,
+    /// The number of physical cores in each NUMA domain.
+    pub cores_per_domain: u32,
+    /// The number of logical CPUs presented by each physical core.
+    pub smt: u32,
+}
+
+impl NodeTopology {
+    /// Returns a node topology wrapped in a result, or an error if any of the
+    /// components of the topology are less than 1.
+    ///
+    /// # Arguments
+    ///
+    /// * `sockets` - The number of CPU sockets in a node.
+    /// * `domains_per_socket` - The number of NUMA domains in each socket.
+    /// * `cores_per_domain` - The number of physical cores in each NUMA domain.
+    /// * `smt` - The number of logical CPUs per physical core, 1 if simultaneous
+    ///   multithreading is not in use.
+    ///
+    /// # Example
+    ///
+    /// A dual socket node with 4 NUMA domains per socket and 16 cores per domain:
+    /// ```
+    /// use pestr::NodeTopology;
+    /// let topology = NodeTopology::new(2, 4, 16, 1).unwrap();
+    /// assert_eq!(topology.cores(), 128);
+    /// ```
+    pub fn new(
+        sockets: u32,
+        domains_per_socket: u32,
+        cores_per_domain: u32,
+        smt: u32,
+    ) -> Result<NodeTopology, GeometryError> {
+        if sockets == 0 || domains_per_socket == 0 || cores_per_domain == 0 {
+            Err(GeometryError {
+                message: String::from("sockets, NUMA domains and cores must be > 0"),
+            })
+        } else if smt == 0 {
+            Err(GeometryError {
+                message: String::from("SMT level must be > 0"),
+            })
+        } else {
+            Ok(NodeTopology {
+                sockets,
+                domains_per_socket,
+                cores_per_domain,
+                smt,
+            })
+        }
+    }
+
+    /// Returns a topology for a node with `cpus_per_node` physical cores split
+    /// evenly over `sockets` sockets and `domains_per_socket` NUMA domains, or
+    /// an error if the cores cannot be split evenly.
+    ///
+    /// # Example
+    /// ```
+    /// use pestr::NodeTopology;
+    /// let topology = NodeTopology::with_cores(128, 2, 4, 1).unwrap();
+    /// assert_eq!(topology.cores_per_domain, 16);
+    /// ```
+    pub fn with_cores(
+        cpus_per_node: u32,
+        sockets: u32,
+        domains_per_socket: u32,
+        smt: u32,
+    ) -> Result<NodeTopology, GeometryError> {
+        if cpus_per_node == 0 {
+            return Err(GeometryError {
+                message: String::from("CPUs per node must be > 0"),
+            });
+        }
+        let domains = sockets * domains_per_socket;
+        if domains == 0 || cpus_per_node % domains != 0 {
+            return Err(GeometryError {
+                message: format!(
+                    "{} CPUs per node cannot be split evenly over {} sockets with {} NUMA domains each",
+                    cpus_per_node, sockets, domains_per_socket
+                ),
+            });
+        }
+        NodeTopology::new(sockets, domains_per_socket, cpus_per_node / domains, smt)
+    }
+
+    /// A topology with a single socket and NUMA domain containing all cores.
+    fn flat(cpus_per_node: u32, smt: u32) -> Result<NodeTopology, GeometryError> {
+        NodeTopology::with_cores(cpus_per_node, 1, 1, smt)
+    }
+
+    /// The number of physical cores in a node.
+    pub fn cores(&self) -> u32 {
+        self.sockets * self.domains_per_socket * self.cores_per_domain
+    }
+
+    /// The number of logical CPUs in a node.
+    pub fn logical_cpus(&self) -> u32 {
+        self.cores() * self.smt
+    }
+
+    /// The number of NUMA domains in a node.
+    pub fn domains(&self) -> u32 {
+        self.sockets * self.domains_per_socket
+    }
+
+    /// The number of logical CPUs in each NUMA domain.
+    pub fn cpus_per_domain(&self) -> u32 {
+        self.cores_per_domain * self.smt
+    }
+
+    /// The number of logical CPUs in each socket.
+    pub fn cpus_per_socket(&self) -> u32 {
+        self.domains_per_socket * self.cpus_per_domain()
+    }
+}
+
+/// The policy used to distribute tasks over the nodes of a reservation.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Distribution {
+    /// Fill each node with as many tasks as it will take, with any remaining
+    /// tasks placed on the last node.
+    Packed,
+    /// Spread tasks evenly over the nodes, with consecutive tasks placed on the
+    /// same node (Slurm's block distribution).
+    Balanced,
+    /// Spread tasks evenly over the nodes, with consecutive tasks placed on
+    /// consecutive nodes in a round-robin fashion.
+    Cyclic,
+}
+
+impl fmt::Display for Distribution {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Distribution::Packed => "packed",
+            Distribution::Balanced => "balanced",
+            Distribution::Cyclic => "cyclic",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Distribution {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "packed" => Ok(Distribution::Packed),
+            "balanced" => Ok(Distribution::Balanced),
+            "cyclic" => Ok(Distribution::Cyclic),
+            _ => Err(format!(
+                "unknown distribution '{}', expected one of packed, balanced, cyclic",
+                s
+            )),
+        }
+    }
+}
+
 /// A job geometry represents the shape of a job (tasks x threads) and the
 /// shape of the resource it is run on.
 #[derive(Clone, Copy, Debug, Serialize)]
 pub struct Geometry {
     cpus_per_node: u32,
-    hyperthreading: bool,
+    smt: u32,
     logical_cpus: u32,
+    reserved_cores: u32,
+    usable_cpus: u32,
+    tasks_per_node: Option<u32>,
+    nodes: Option<u32>,
+    distribution: Distribution,
+    topology: NodeTopology,
     /// The number of MPI tasks (PEs in Cray language) the job uses.
     pub tasks: u32,
     /// The number of threads allocated to each MPI task.
@@ -40,35 +244,65 @@ impl Geometry {
     /// than 1, and those where `threads` is greater than the number of logical CPUS
     /// available on a node.
     ///
+    /// The node is assumed to have a single socket and NUMA domain, use
+    /// [`Geometry::from_topology`] to describe a more detailed node layout.
+    ///
     /// # Arguments
     ///
     /// * `cpus_per_node` - The number of physical CPU cores available per node.
-    /// * `hyperthreading` - Whether or not hyperthreading is active, if it is the
-    ///                      number of logical CPUs will be double the number given
-    ///                      by `cpus_per_node`.
+    /// * `smt` - The number of logical CPUs per physical core, for example `2` if
+    ///   hyperthreading is active or `1` if it is not. The number of logical CPUs
+    ///   per node is `smt` times the number given by `cpus_per_node`.
     /// * `tasks` - The number of MPI tasks (PEs) the job is allocated.
     /// * `threads` - The number of threads each MPI task is allocated.
     ///
     /// # Example
     /// ```
     /// use pestr::Geometry;
-    /// let geom = match Geometry::new(36, true, 24, 4) {
+    /// let geom = match Geometry::new(36, 2, 24, 4) {
     ///     Ok(geom) => geom,
     ///     Err(e) => panic!("failed to create geometry")
     /// };
     /// ```
+    ///
+    /// A node with 22 physical cores running with SMT4 has 88 logical CPUs:
+    /// ```
+    /// use pestr::{Geometry, Reservation};
+    /// let geom = Geometry::new(22, 4, 44, 8).unwrap();
+    /// assert_eq!(Reservation::from_geometry(geom).cpus, 352);
+    /// ```
     pub fn new(
         cpus_per_node: u32,
-        hyperthreading: bool,
+        smt: u32,
         tasks: u32,
         threads: u32,
     ) -> Result<Geometry, GeometryError> {
-        let logical_cpus = cpus_per_node * if hyperthreading { 2 } else { 1 };
-        if cpus_per_node == 0 {
-            Err(GeometryError {
-                message: String::from("CPUs per node must be > 0"),
-            })
-        } else if tasks == 0 || threads == 0 {
+        let topology = NodeTopology::flat(cpus_per_node, smt)?;
+        Geometry::from_topology(topology, tasks, threads)
+    }
+
+    /// Returns a geometry wrapped in a result for a job running on nodes with
+    /// the given topology, or an error if the geometry is invalid.
+    ///
+    /// # Arguments
+    ///
+    /// * `topology` - The layout of the CPUs within each node.
+    /// * `tasks` - The number of MPI tasks (PEs) the job is allocated.
+    /// * `threads` - The number of threads each MPI task is allocated.
+    ///
+    /// # Example
+    /// ```
+    /// use pestr::{Geometry, NodeTopology};
+    /// let topology = NodeTopology::new(2, 4, 16, 1).unwrap();
+    /// let geom = Geometry::from_topology(topology, 512, 16).unwrap();
+    /// ```
+    pub fn from_topology(
+        topology: NodeTopology,
+        tasks: u32,
+        threads: u32,
+    ) -> Result<Geometry, GeometryError> {
+        let logical_cpus = topology.logical_cpus();
+        if tasks == 0 || threads == 0 {
             Err(GeometryError {
                 message: String::from("tasks and threads must be > 0"),
             })
@@ -78,15 +312,245 @@ impl Geometry {
             })
         } else {
             Ok(Geometry {
-                cpus_per_node,
-                hyperthreading,
+                cpus_per_node: topology.cores(),
+                smt: topology.smt,
                 logical_cpus,
+                reserved_cores: 0,
+                usable_cpus: logical_cpus,
+                tasks_per_node: None,
+                nodes: None,
+                distribution: Distribution::Packed,
+                topology,
                 tasks,
                 threads,
             })
         }
     }
 
+    /// Returns a copy of this geometry with some physical cores on each node
+    /// reserved for system use (core specialisation), or an error if the
+    /// remaining cores cannot accommodate a single task.
+    ///
+    /// Reserved cores are never used by the job, they are counted separately
+    /// from idle CPUs in the reservation.
+    ///
+    /// # Arguments
+    ///
+    /// * `reserved_cores` - The number of physical cores per node reserved for
+    ///   the system, the equivalent of Slurm's `CoreSpecCount`.
+    ///
+    /// # Example
+    /// ```
+    /// use pestr::{Geometry, Reservation};
+    /// let geom = Geometry::new(128, 1, 63, 2).unwrap().with_reserved_cores(2).unwrap();
+    /// let res = Reservation::from_geometry(geom);
+    /// assert_eq!(res.nodes, 1);
+    /// assert_eq!(res.reserved_cpus, 2);
+    /// assert_eq!(res.idle_cpus, 0);
+    /// ```
+    pub fn with_reserved_cores(self, reserved_cores: u32) -> Result<Geometry, GeometryError> {
+        if reserved_cores >= self.cpus_per_node {
+            return Err(GeometryError {
+                message: String::from("reserved cores must be fewer than the CPUs per node"),
+            });
+        }
+        let usable_cpus = (self.cpus_per_node - reserved_cores) * self.smt;
+        if self.threads * self.tasks_per_node.unwrap_or(1) > usable_cpus {
+            Err(GeometryError {
+                message: String::from(
+                    "threads cannot be larger than the number of usable CPUs per node",
+                ),
+            })
+        } else {
+            Ok(Geometry {
+                reserved_cores,
+                usable_cpus,
+                ..self
+            })
+        }
+    }
+
+    /// Returns a copy of this geometry that places at most `tasks_per_node` tasks
+    /// on each node, or an error if that many tasks will not fit on a node.
+    ///
+    /// This is used to describe jobs that deliberately underpopulate their nodes,
+    /// the CPUs left unpopulated are counted separately from idle CPUs in the
+    /// reservation.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks_per_node` - The number of tasks to place on each node, the
+    ///   equivalent of Slurm's `--ntasks-per-node`.
+    ///
+    /// # Example
+    /// ```
+    /// use pestr::{Geometry, Reservation};
+    /// let geom = Geometry::new(128, 1, 64, 4).unwrap().with_tasks_per_node(16).unwrap();
+    /// let res = Reservation::from_geometry(geom);
+    /// assert_eq!(res.nodes, 4);
+    /// assert_eq!(res.underpopulated_cpus, 256);
+    /// assert!(res.is_filled());
+    /// ```
+    pub fn with_tasks_per_node(self, tasks_per_node: u32) -> Result<Geometry, GeometryError> {
+        if tasks_per_node == 0 {
+            Err(GeometryError {
+                message: String::from("tasks per node must be > 0"),
+            })
+        } else if tasks_per_node
+            .checked_mul(self.threads)
+            .map_or(true, |cpus| cpus > self.usable_cpus)
+        {
+            Err(GeometryError {
+                message: format!(
+                    "{} tasks of {} threads will not fit on a node with {} usable CPUs",
+                    tasks_per_node, self.threads, self.usable_cpus
+                ),
+            })
+        } else {
+            Ok(Geometry {
+                tasks_per_node: Some(tasks_per_node),
+                ..self
+            })
+        }
+    }
+
+    /// Returns a copy of this geometry that runs on `nodes` nodes, or an error
+    /// if the tasks will not fit on that many nodes.
+    ///
+    /// Nodes beyond those needed to hold the tasks are still part of the
+    /// reservation, so the CPUs on them are counted as idle. How the tasks are
+    /// spread over the nodes is set by [`Geometry::with_distribution`].
+    ///
+    /// # Arguments
+    ///
+    /// * `nodes` - The number of nodes to run on, the equivalent of Slurm's
+    ///   `--nodes`.
+    ///
+    /// # Example
+    /// ```
+    /// use pestr::{Distribution, Geometry, Reservation};
+    /// let geom = Geometry::new(128, 1, 128, 12)
+    ///     .unwrap()
+    ///     .with_nodes(20)
+    ///     .unwrap()
+    ///     .with_distribution(Distribution::Balanced);
+    /// let res = Reservation::from_geometry(geom);
+    /// assert_eq!((res.nodes, res.cpus, res.idle_cpus), (20, 2560, 1024));
+    /// ```
+    pub fn with_nodes(self, nodes: u32) -> Result<Geometry, GeometryError> {
+        if nodes == 0 {
+            Err(GeometryError {
+                message: String::from("nodes must be > 0"),
+            })
+        } else if nodes.checked_mul(self.logical_cpus).is_none() {
+            Err(GeometryError {
+                message: format!(
+                    "{} nodes of {} logical CPUs are too many CPUs to count",
+                    nodes, self.logical_cpus
+                ),
+            })
+        } else if self.tasks > nodes * self.tasks_per_node() {
+            Err(GeometryError {
+                message: format!(
+                    "{} tasks will not fit on {} nodes with {} tasks per node",
+                    self.tasks,
+                    nodes,
+                    self.tasks_per_node()
+                ),
+            })
+        } else {
+            Ok(Geometry {
+                nodes: Some(nodes),
+                ..self
+            })
+        }
+    }
+
+    /// Returns a copy of this geometry that distributes its tasks over nodes
+    /// according to the given policy, by default tasks are packed.
+    ///
+    /// The distribution does not change the number of nodes a job needs, only
+    /// how many tasks are placed on each of them.
+    ///
+    /// # Example
+    /// ```
+    /// use pestr::{Distribution, Geometry, Reservation};
+    /// let geom = Geometry::new(128, 1, 300, 1).unwrap();
+    /// let packed = Reservation::from_geometry(geom);
+    /// assert_eq!((packed.min_node_tasks, packed.max_node_tasks), (44, 128));
+    /// let geom = geom.with_distribution(Distribution::Balanced);
+    /// let balanced = Reservation::from_geometry(geom);
+    /// assert_eq!((balanced.min_node_tasks, balanced.max_node_tasks), (100, 100));
+    /// ```
+    pub fn with_distribution(self, distribution: Distribution) -> Geometry {
+        Geometry {
+            distribution,
+            ..self
+        }
+    }
+
+    /// The layout of the CPUs within each node the job runs on.
+    pub fn topology(&self) -> NodeTopology {
+        self.topology
+    }
+
+    /// The number of logical CPUs per physical core.
+    pub fn smt(&self) -> u32 {
+        self.smt
+    }
+
+    /// The number of physical cores per node reserved for system use.
+    pub fn reserved_cores(&self) -> u32 {
+        self.reserved_cores
+    }
+
+    /// The number of logical CPUs per node available to the job.
+    pub fn usable_cpus(&self) -> u32 {
+        self.usable_cpus
+    }
+
+    /// The number of tasks placed on each fully populated node, either the
+    /// number requested with [`Geometry::with_tasks_per_node`] or as many as
+    /// will fit on a node.
+    pub fn tasks_per_node(&self) -> u32 {
+        self.tasks_per_node
+            .unwrap_or(self.usable_cpus / self.threads)
+    }
+
+    // The number of usable CPUs per node left unused by deliberately placing
+    // fewer tasks on each node than would fit.
+    fn unpopulated_cpus_per_node(&self) -> u32 {
+        (self.usable_cpus / self.threads - self.tasks_per_node()) * self.threads
+    }
+
+    /// The placement of this geometry's tasks under a binding policy, or an
+    /// error if the binding cannot place every task on a node, this is the
+    /// same as [`Placement::new`].
+    ///
+    /// # Example
+    /// ```
+    /// use pestr::{Binding, Distribution, Geometry};
+    /// let geom = Geometry::new(128, 1, 4096, 4)
+    ///     .unwrap()
+    ///     .with_distribution(Distribution::Cyclic);
+    /// let rank = geom.placement(Binding::Compact).unwrap().rank(3171).unwrap();
+    /// assert_eq!((rank.node, rank.local_rank), (99, 24));
+    /// ```
+    pub fn placement(self, binding: Binding) -> Result<Placement, GeometryError> {
+        Placement::new(self, binding)
+    }
+
+    /// The policy used to distribute tasks over nodes.
+    pub fn distribution(&self) -> Distribution {
+        self.distribution
+    }
+
+    /// Returns `true` if the number of tasks per node has been deliberately
+    /// set lower than the number that would fit on a node.
+    pub fn is_underpopulated(&self) -> bool {
+        self.tasks_per_node() < self.usable_cpus / self.threads
+    }
+
     /// For a given geometry produce alternate geometries along with their
     /// reservations, that are within a particular size similarity threshold
     /// and fill their whole reservation.
@@ -94,30 +558,29 @@ impl Geometry {
     /// # Arguments
     ///
     /// * `task_radius` - The search distance for task count expressed as a fraction
-    ///                   of the geometry's task count. For example, a value of `0.5`
-    ///                   allows alternate geometries with up to 50% more or fewer
-    ///                   tasks than this one.
+    ///   of the geometry's task count. For example, a value of `0.5` allows alternate
+    ///   geometries with up to 50% more or fewer tasks than this one.
     /// * `thread_radius` - The search distance for thread count expressed as a fraction
-    ///                     of the geometry's thread count.
+    ///   of the geometry's thread count.
     /// * `filter` - A filter function accepting a geometry and a reservation as inputs
-    ///              that returns `true` if the geometry should be used, or `false` if
-    ///              it should be ignored. This can be used to restrict the alternates
-    ///              to a subset, for example it can be used to select only geometries
-    ///              that have the same size reservation as this one.
+    ///   that returns `true` if the geometry should be used, or `false` if it should be
+    ///   ignored. This can be used to restrict the alternates to a subset, for example
+    ///   it can be used to select only geometries that have the same size reservation
+    ///   as this one.
     ///
     /// # Examples
     ///
     /// Suggest all alternates with 12-36 tasks and 2-6 threads:
     /// ```
     /// use pestr::Geometry;
-    /// let geom = Geometry::new(36, false, 24, 4).unwrap();
+    /// let geom = Geometry::new(36, 1, 24, 4).unwrap();
     /// let alternates = geom.alternates(0.25, 0.5, &|_, _| true);
     /// ```
     ///
     /// Suggest only alternates that have the same size reservation as the current one:
     /// ```
     /// use pestr::{Geometry, Reservation};
-    /// let geom = Geometry::new(36, false, 120, 6).unwrap();
+    /// let geom = Geometry::new(36, 1, 120, 6).unwrap();
     /// let res = Reservation::from_geometry(geom);
     /// let alternates = geom.alternates(0.25, 0.5, &|_, r| { r.nodes == res.nodes });
     /// ```
@@ -127,32 +590,91 @@ impl Geometry {
         thread_radius: f32,
         filter: &dyn Fn(Geometry, Reservation) -> bool,
     ) -> Vec<(Geometry, Reservation)> {
-        let task_delta = (task_radius * (self.tasks as f32)) as i64;
-        let thread_delta = (thread_radius * (self.threads as f32)) as i64;
+        let tasks: Vec<u32> = Self::radius_range(self.tasks, task_radius).collect();
+        let threads: Vec<u32> = Self::radius_range(self.threads, thread_radius).collect();
+        self.alternates_among(&tasks, &threads, filter)
+    }
+
+    /// For a given geometry produce alternate geometries along with their
+    /// reservations, with task and thread counts taken from the given lists,
+    /// that fill their whole reservation.
+    ///
+    /// # Arguments
+    ///
+    /// * `tasks` - The task counts to consider.
+    /// * `threads` - The thread counts to consider, counts that are too large
+    ///   to fit a task on a node are ignored.
+    /// * `filter` - A filter function accepting a geometry and a reservation as
+    ///   inputs that returns `true` if the geometry should be used, as for
+    ///   [`Geometry::alternates`].
+    ///
+    /// # Examples
+    ///
+    /// Suggest alternates with 96 to 160 tasks and 4, 8 or 16 threads:
+    /// ```
+    /// use pestr::Geometry;
+    /// let geom = Geometry::new(128, 1, 128, 12).unwrap();
+    /// let tasks: Vec<u32> = (96..=160).collect();
+    /// let alternates = geom.alternates_among(&tasks, &[4, 8, 16], &|_, _| true);
+    /// assert!(alternates.iter().any(|(g, r)| g.tasks == 96 && g.threads == 8 && r.nodes == 6));
+    /// assert!(alternates.iter().all(|(g, _)| [4, 8, 16].contains(&g.threads)));
+    /// assert!(alternates.iter().all(|(g, _)| (96..=160).contains(&g.tasks)));
+    /// ```
+    ///
+    /// Restrict the alternates to at most 10 nodes with the filter:
+    /// ```
+    /// use pestr::Geometry;
+    /// let geom = Geometry::new(128, 1, 128, 12).unwrap();
+    /// let tasks: Vec<u32> = (96..=160).collect();
+    /// let alternates = geom.alternates_among(&tasks, &[4, 8, 16], &|_, r| r.nodes <= 10);
+    /// assert!(alternates.iter().all(|(_, r)| r.nodes <= 10));
+    /// assert!(alternates.iter().all(|(g, _)| g.threads != 16));
+    /// ```
+    ///
+    /// Keep the number of threads and search only the number of tasks:
+    /// ```
+    /// use pestr::Geometry;
+    /// let geom = Geometry::new(128, 1, 128, 16).unwrap();
+    /// let tasks: Vec<u32> = (116..=140).collect();
+    /// let alternates = geom.alternates_among(&tasks, &[geom.threads], &|_, _| true);
+    /// let sizes: Vec<(u32, u32)> = alternates.iter().map(|(g, r)| (g.tasks, r.nodes)).collect();
+    /// assert_eq!(sizes, vec![(120, 15), (128, 16), (136, 17)]);
+    /// ```
+    pub fn alternates_among(
+        self,
+        tasks: &[u32],
+        threads: &[u32],
+        filter: &dyn Fn(Geometry, Reservation) -> bool,
+    ) -> Vec<(Geometry, Reservation)> {
+        let max_threads = self.usable_cpus / self.tasks_per_node.unwrap_or(1);
         let mut alternates = Vec::new();
-        for task_p in -task_delta..=task_delta {
-            let tasks = (self.tasks as i64) + task_p;
-            if tasks < 1 {
-                continue;
-            }
-            for thread_p in -thread_delta..=thread_delta {
-                let threads = (self.threads as i64) + thread_p;
-                if threads < 1 || threads > self.logical_cpus as i64 {
-                    continue;
-                }
-                let geom = Geometry::with_tasks_and_threads(self, tasks as u32, threads as u32);
+        for &task_count in tasks.iter().filter(|&&t| t >= 1) {
+            for &thread_count in threads.iter().filter(|&&t| t >= 1 && t <= max_threads) {
+                let geom = Geometry::with_tasks_and_threads(self, task_count, thread_count);
                 let res = Reservation::from_geometry(geom);
                 if res.is_filled && filter(geom, res) {
                     alternates.push((geom, res));
                 }
             }
         }
-        alternates.sort_by(|(_, a), (_, b)| a.nodes.cmp(&b.nodes));
+        alternates.sort_by_key(|(_, r)| r.nodes);
         alternates
     }
 
+    /// The range of counts within `radius` of `count`, where `radius` is a
+    /// fraction of `count`, counts below 1 are excluded.
+    pub fn radius_range(count: u32, radius: f32) -> std::ops::RangeInclusive<u32> {
+        let delta = (radius * (count as f32)) as u32;
+        count.saturating_sub(delta).max(1)..=count.saturating_add(delta)
+    }
+
     fn with_tasks_and_threads(geom: Geometry, tasks: u32, threads: u32) -> Geometry {
-        Geometry::new(geom.cpus_per_node, geom.hyperthreading, tasks, threads).unwrap()
+        Geometry {
+            tasks,
+            threads,
+            nodes: None,
+            ..geom
+        }
     }
 }
 
@@ -168,13 +690,30 @@ pub struct Reservation {
     pub used_cpus: u32,
     /// The number of logical CPUs that are reserved but idle.
     pub idle_cpus: u32,
+    /// The number of logical CPUs set aside for system use and unavailable to the job.
+    pub reserved_cpus: u32,
+    /// The number of logical CPUs deliberately left unused by placing fewer tasks
+    /// on each node than would fit.
+    pub underpopulated_cpus: u32,
     /// The number of nodes in the reservation that have 1 or more idle CPUs in them.
     pub partial_nodes: u32,
+    /// The largest number of tasks placed on any one node.
+    pub max_node_tasks: u32,
+    /// The smallest number of tasks placed on any one node.
+    pub min_node_tasks: u32,
+    /// The number of tasks whose threads are spread over more than one NUMA domain.
+    pub domain_crossings: u32,
+    /// The number of tasks whose threads are spread over more than one socket.
+    pub socket_crossings: u32,
 }
 
 impl Reservation {
     /// Create a reservation from a geometry.
     ///
+    /// Tasks are assumed to be packed onto each node in order, with the threads
+    /// of each task occupying consecutive logical CPUs, when counting the tasks
+    /// that cross NUMA domain and socket boundaries.
+    ///
     /// # Arguments
     ///
     /// * geom - A geometry to construct a reservation from.
@@ -182,43 +721,555 @@ impl Reservation {
     /// # Examples
     /// ```
     /// use pestr::{Geometry, Reservation};
-    /// let geom = Geometry::new(36, false, 24, 4).unwrap();
+    /// let geom = Geometry::new(36, 1, 24, 4).unwrap();
     /// let res = Reservation::from_geometry(geom);
     /// ```
+    ///
+    /// Count the tasks that straddle NUMA domains on a node with 4 domains of
+    /// 16 cores each:
+    /// ```
+    /// use pestr::{Geometry, NodeTopology, Reservation};
+    /// let topology = NodeTopology::new(1, 4, 16, 1).unwrap();
+    /// let geom = Geometry::from_topology(topology, 10, 6).unwrap();
+    /// let res = Reservation::from_geometry(geom);
+    /// assert_eq!(res.domain_crossings, 2);
+    /// assert_eq!(res.socket_crossings, 0);
+    /// ```
     pub fn from_geometry(geom: Geometry) -> Reservation {
-        fn compute_cpu_list(geom: Geometry) -> Vec<u32> {
-            let tasks_per_node = geom.logical_cpus / geom.threads;
-            let num_nodes = geom.tasks / tasks_per_node;
-            let remainder = geom.tasks - (num_nodes * tasks_per_node);
-            let mut nodes = vec![tasks_per_node * geom.threads; num_nodes as usize];
-            if remainder > 0 {
-                nodes.push(remainder * geom.threads);
-            }
-            nodes
+        fn count_crossings(tasks: u32, threads: u32, span: u32) -> u32 {
+            (0..tasks)
+                .filter(|task| {
+                    let first = task * threads;
+                    let last = first + threads - 1;
+                    first / span != last / span
+                })
+                .count() as u32
         }
-        let cpu_list = compute_cpu_list(geom);
-        let reserved_nodes: u32 = cpu_list.len() as u32;
+        let runs = Reservation::node_runs(geom);
+        let max_node_tasks = runs.iter().map(|run| run.node.tasks).max().unwrap();
+        let min_node_tasks = runs.iter().map(|run| run.node.tasks).min().unwrap();
+        let crossings = |span: u32| {
+            runs.iter()
+                .map(|run| run.count * count_crossings(run.node.tasks, geom.threads, span))
+                .sum()
+        };
+        let domain_crossings = crossings(geom.topology.cpus_per_domain());
+        let socket_crossings = crossings(geom.topology.cpus_per_socket());
+        let reserved_nodes: u32 = runs.iter().map(|run| run.count).sum();
         let reserved_cpus = reserved_nodes * geom.logical_cpus;
-        let used_cpus = cpu_list.iter().sum();
-        if reserved_cpus == used_cpus {
+        let system_cpus = reserved_nodes * geom.reserved_cores * geom.smt;
+        let underpopulated_cpus = reserved_nodes * geom.unpopulated_cpus_per_node();
+        let usable_cpus = reserved_nodes * geom.usable_cpus - underpopulated_cpus;
+        let used_cpus = runs.iter().map(|run| run.count * run.node.used_cpus).sum();
+        if usable_cpus == used_cpus {
             Reservation {
                 nodes: reserved_nodes,
                 cpus: reserved_cpus,
                 is_filled: true,
-                used_cpus: reserved_cpus,
+                used_cpus: usable_cpus,
                 idle_cpus: 0,
+                reserved_cpus: system_cpus,
+                underpopulated_cpus,
                 partial_nodes: 0,
+                max_node_tasks,
+                min_node_tasks,
+                domain_crossings,
+                socket_crossings,
             }
         } else {
-            let partial_nodes = cpu_list.iter().filter(|&n| *n < geom.logical_cpus).count() as u32;
+            let partial_nodes = runs
+                .iter()
+                .filter(|run| run.node.idle_cpus > 0)
+                .map(|run| run.count)
+                .sum();
             Reservation {
                 nodes: reserved_nodes,
                 cpus: reserved_cpus,
                 is_filled: false,
                 used_cpus,
-                idle_cpus: reserved_cpus - used_cpus,
+                idle_cpus: usable_cpus - used_cpus,
+                reserved_cpus: system_cpus,
+                underpopulated_cpus,
                 partial_nodes,
+                max_node_tasks,
+                min_node_tasks,
+                domain_crossings,
+                socket_crossings,
+            }
+        }
+    }
+
+    /// Returns `true` if every usable CPU in the reservation is used by the job.
+    pub fn is_filled(&self) -> bool {
+        self.is_filled
+    }
+
+    /// The difference between the largest and smallest number of tasks placed
+    /// on a node.
+    pub fn imbalance(&self) -> u32 {
+        self.max_node_tasks - self.min_node_tasks
+    }
+
+    /// List the usage of each node in the reservation for a geometry, in node order.
+    ///
+    /// # Arguments
+    ///
+    /// * geom - The geometry to lay out over nodes.
+    ///
+    /// # Examples
+    /// ```
+    /// use pestr::{Geometry, Reservation};
+    /// let geom = Geometry::new(128, 1, 20, 12).unwrap();
+    /// for (i, node) in Reservation::node_usage(geom).iter().enumerate() {
+    ///     println!("node {}: {} tasks, {} CPUs idle", i, node.tasks, node.idle_cpus);
+    /// }
+    /// ```
+    pub fn node_usage(geom: Geometry) -> Vec<NodeUsage> {
+        Reservation::node_runs(geom)
+            .into_iter()
+            .flat_map(|run| std::iter::repeat(run.node).take(run.count as usize))
+            .collect()
+    }
+
+    /// List the usage of the nodes in the reservation for a geometry, with
+    /// consecutive nodes that are used identically grouped into a single run.
+    ///
+    /// # Arguments
+    ///
+    /// * geom - The geometry to lay out over nodes.
+    ///
+    /// # Examples
+    /// ```
+    /// use pestr::{Geometry, Reservation};
+    /// let geom = Geometry::new(128, 1, 128, 12).unwrap();
+    /// let runs = Reservation::node_runs(geom);
+    /// assert_eq!(runs.len(), 2);
+    /// assert_eq!((runs[0].count, runs[0].node.tasks), (12, 10));
+    /// assert_eq!((runs[1].count, runs[1].node.tasks), (1, 8));
+    /// ```
+    pub fn node_runs(geom: Geometry) -> Vec<NodeRun> {
+        let populated_cpus = geom.usable_cpus - geom.unpopulated_cpus_per_node();
+        let mut runs: Vec<NodeRun> = Vec::new();
+        for (count, tasks) in node_task_runs(geom)
+            .into_iter()
+            .filter(|&(count, _)| count > 0)
+        {
+            let used_cpus = tasks * geom.threads;
+            let node = NodeUsage {
+                tasks,
+                used_cpus,
+                idle_cpus: populated_cpus - used_cpus,
+            };
+            match runs.last_mut() {
+                Some(run) if run.node == node => run.count += count,
+                _ => runs.push(NodeRun { count, node }),
             }
         }
+        runs
+    }
+}
+
+/// The policy used to bind the threads of each task to logical CPUs within a node.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Binding {
+    /// Place tasks one after another on consecutive CPUs, filling the logical
+    /// CPUs of each core before moving to the next core.
+    Compact,
+    /// Place consecutive tasks in different NUMA domains in a round-robin
+    /// fashion, with the threads of each task kept within one domain.
+    Scatter,
+    /// Split the tasks on a node into equal blocks, one per socket, with the
+    /// tasks in each block packed onto the cores of their socket.
+    Spread,
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let name = match self {
+            Binding::Compact => "compact",
+            Binding::Scatter => "scatter",
+            Binding::Spread => "spread",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl FromStr for Binding {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "compact" => Ok(Binding::Compact),
+            "scatter" => Ok(Binding::Scatter),
+            "spread" => Ok(Binding::Spread),
+            _ => Err(format!(
+                "unknown binding '{}', expected one of compact, scatter, spread",
+                s
+            )),
+        }
+    }
+}
+
+/// The logical CPUs a single task (rank) and its threads are bound to.
+#[derive(Clone, Debug, PartialEq, Eq, Serialize)]
+pub struct RankPlacement {
+    /// The global rank of the task.
+    pub rank: u32,
+    /// The index of the node the task is placed on.
+    pub node: u32,
+    /// The rank of the task amongst the tasks on its node.
+    pub local_rank: u32,
+    /// The logical CPU each thread of the task is bound to, in thread order.
+    pub cpus: Vec<u32>,
+}
+
+impl RankPlacement {
+    /// The logical CPUs used by the task as a hexadecimal CPU mask, where bit
+    /// `n` is set if logical CPU `n` is used.
+    ///
+    /// # Example
+    /// ```
+    /// use pestr::{Binding, Geometry, Placement};
+    /// let geom = Geometry::new(16, 1, 4, 4).unwrap();
+    /// let placement = Placement::new(geom, Binding::Compact).unwrap();
+    /// assert_eq!(placement.rank(1).unwrap().mask(), "0xf0");
+    /// ```
+    pub fn mask(&self) -> String {
+        let max_cpu = self.cpus.iter().max().copied().unwrap_or(0);
+        let mut nibbles = vec![0u8; (max_cpu / 4 + 1) as usize];
+        for &cpu in &self.cpus {
+            nibbles[(cpu / 4) as usize] |= 1 << (cpu % 4);
+        }
+        let digits: String = nibbles
+            .iter()
+            .rev()
+            .map(|&n| std::char::from_digit(n as u32, 16).unwrap())
+            .collect();
+        format!("0x{}", digits)
+    }
+
+    /// The logical CPUs used by the task as an OpenMP `OMP_PLACES` list, with
+    /// each run of consecutive CPUs written as a single place `{first:count}`.
+    ///
+    /// # Example
+    /// ```
+    /// use pestr::{Binding, Geometry, Placement};
+    /// let geom = Geometry::new(16, 1, 4, 4).unwrap();
+    /// let placement = Placement::new(geom, Binding::Compact).unwrap();
+    /// assert_eq!(placement.rank(1).unwrap().places(), "{4:4}");
+    /// ```
+    pub fn places(&self) -> String {
+        let mut runs: Vec<(u32, u32)> = Vec::new();
+        for &cpu in &self.cpus {
+            match runs.last_mut() {
+                Some((first, count)) if *first + *count == cpu => *count += 1,
+                _ => runs.push((cpu, 1)),
+            }
+        }
+        runs.iter()
+            .map(|&(first, count)| format!("{{{}:{}}}", first, count))
+            .collect::<Vec<String>>()
+            .join(",")
+    }
+}
+
+/// The logical CPU a single thread of a task is bound to.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct ThreadLocation {
+    /// The global rank of the task.
+    pub rank: u32,
+    /// The index of the thread within its task.
+    pub thread: u32,
+    /// The index of the node the thread runs on.
+    pub node: u32,
+    /// The logical CPU the thread is bound to.
+    pub cpu: u32,
+}
+
+/// A placement maps each task of a geometry, and each of its threads, to the
+/// node and logical CPUs it runs on.
+///
+/// Logical CPUs are numbered the way Linux typically numbers them: the first
+/// logical CPU of each physical core is numbered from 0 upwards in core order,
+/// followed by the second logical CPU of each core and so on. On a node with 64
+/// cores and SMT2, logical CPUs 5 and 69 are on the same physical core. Cores
+/// reserved for the system are assumed to be the last cores on the node.
+#[derive(Clone, Debug)]
+pub struct Placement {
+    geom: Geometry,
+    binding: Binding,
+    node_tasks: Vec<u32>,
+    node_starts: Vec<u32>,
+    layouts: Vec<(u32, Vec<Vec<u32>>)>,
+}
+
+impl Placement {
+    /// Returns the placement of a geometry's tasks under a binding policy, or
+    /// an error if the binding cannot place every task on a node.
+    ///
+    /// # Arguments
+    ///
+    /// * `geom` - The geometry to place.
+    /// * `binding` - The policy used to bind threads to logical CPUs.
+    ///
+    /// # Examples
+    ///
+    /// Spread 4 tasks of 2 threads over the two sockets of a node:
+    /// ```
+    /// use pestr::{Binding, Geometry, NodeTopology, Placement};
+    /// let topology = NodeTopology::new(2, 1, 8, 1).unwrap();
+    /// let geom = Geometry::from_topology(topology, 4, 2).unwrap();
+    /// let placement = Placement::new(geom, Binding::Spread).unwrap();
+    /// let cpus: Vec<Vec<u32>> = placement.ranks().map(|r| r.cpus).collect();
+    /// assert_eq!(cpus, vec![vec![0, 1], vec![2, 3], vec![8, 9], vec![10, 11]]);
+    /// ```
+    pub fn new(geom: Geometry, binding: Binding) -> Result<Placement, GeometryError> {
+        let node_tasks = node_task_counts(geom);
+        let node_starts = node_tasks
+            .iter()
+            .scan(0, |start, &n| {
+                let node_start = *start;
+                *start += n;
+                Some(node_start)
+            })
+            .collect();
+        let mut layouts: Vec<(u32, Vec<Vec<u32>>)> = Vec::new();
+        for &tasks in &node_tasks {
+            if !layouts.iter().any(|(n, _)| *n == tasks) {
+                layouts.push((tasks, node_layout(geom, binding, tasks)?));
+            }
+        }
+        Ok(Placement {
+            geom,
+            binding,
+            node_tasks,
+            node_starts,
+            layouts,
+        })
+    }
+
+    /// The geometry being placed.
+    pub fn geometry(&self) -> Geometry {
+        self.geom
+    }
+
+    /// The policy used to bind threads to logical CPUs.
+    pub fn binding(&self) -> Binding {
+        self.binding
+    }
+
+    /// The placement of a single task, or `None` if the rank is not part of
+    /// the job.
+    pub fn rank(&self, rank: u32) -> Option<RankPlacement> {
+        if rank >= self.geom.tasks {
+            return None;
+        }
+        let (node, local_rank) = match self.geom.distribution {
+            Distribution::Cyclic => {
+                let nodes = self.node_tasks.len() as u32;
+                (rank % nodes, rank / nodes)
+            }
+            Distribution::Packed | Distribution::Balanced => {
+                let node = self.node_starts.partition_point(|&s| s <= rank) - 1;
+                (node as u32, rank - self.node_starts[node])
+            }
+        };
+        Some(RankPlacement {
+            rank,
+            node,
+            local_rank,
+            cpus: self.local_cpus(node, local_rank).to_vec(),
+        })
+    }
+
+    /// The location of a single thread of a task, or `None` if the rank or
+    /// thread is not part of the job.
+    ///
+    /// # Example
+    /// ```
+    /// use pestr::{Binding, Geometry, Placement};
+    /// let geom = Geometry::new(128, 1, 4096, 4).unwrap();
+    /// let placement = Placement::new(geom, Binding::Compact).unwrap();
+    /// let location = placement.thread(3171, 2).unwrap();
+    /// assert_eq!((location.node, location.cpu), (99, 14));
+    /// ```
+    pub fn thread(&self, rank: u32, thread: u32) -> Option<ThreadLocation> {
+        let placement = self.rank(rank)?;
+        let &cpu = placement.cpus.get(thread as usize)?;
+        Some(ThreadLocation {
+            rank,
+            thread,
+            node: placement.node,
+            cpu,
+        })
+    }
+
+    /// The task and thread bound to a logical CPU on a node, or `None` if
+    /// the CPU is not used by the job.
+    ///
+    /// # Example
+    /// ```
+    /// use pestr::{Binding, Geometry, Placement};
+    /// let geom = Geometry::new(128, 1, 4096, 4).unwrap();
+    /// let placement = Placement::new(geom, Binding::Compact).unwrap();
+    /// let location = placement.rank_at(99, 14).unwrap();
+    /// assert_eq!((location.rank, location.thread), (3171, 2));
+    /// ```
+    pub fn rank_at(&self, node: u32, cpu: u32) -> Option<ThreadLocation> {
+        self.node(node).into_iter().find_map(|placement| {
+            placement
+                .cpus
+                .iter()
+                .position(|&c| c == cpu)
+                .map(|thread| ThreadLocation {
+                    rank: placement.rank,
+                    thread: thread as u32,
+                    node,
+                    cpu,
+                })
+        })
+    }
+
+    /// Returns `true` if a logical CPU on a node belongs to a core reserved
+    /// for system use.
+    pub fn is_reserved(&self, cpu: u32) -> bool {
+        let cores = self.geom.topology.cores();
+        cpu % cores >= cores - self.geom.reserved_cores
+    }
+
+    /// The placement of every task in the job, in rank order.
+    pub fn ranks(&self) -> impl Iterator<Item = RankPlacement> + '_ {
+        (0..self.geom.tasks).filter_map(move |rank| self.rank(rank))
+    }
+
+    /// The placement of the tasks on a single node, in local rank order.
+    pub fn node(&self, node: u32) -> Vec<RankPlacement> {
+        let tasks = match self.node_tasks.get(node as usize) {
+            Some(&tasks) => tasks,
+            None => return Vec::new(),
+        };
+        let nodes = self.node_tasks.len() as u32;
+        (0..tasks)
+            .map(|local_rank| RankPlacement {
+                rank: match self.geom.distribution {
+                    Distribution::Cyclic => local_rank * nodes + node,
+                    Distribution::Packed | Distribution::Balanced => {
+                        self.node_starts[node as usize] + local_rank
+                    }
+                },
+                node,
+                local_rank,
+                cpus: self.local_cpus(node, local_rank).to_vec(),
+            })
+            .collect()
+    }
+
+    fn local_cpus(&self, node: u32, local_rank: u32) -> &[u32] {
+        let tasks = self.node_tasks[node as usize];
+        let (_, layout) = self.layouts.iter().find(|(n, _)| *n == tasks).unwrap();
+        &layout[local_rank as usize]
+    }
+}
+
+// The logical CPUs each task on a node with the given number of tasks is bound to.
+fn node_layout(
+    geom: Geometry,
+    binding: Binding,
+    tasks: u32,
+) -> Result<Vec<Vec<u32>>, GeometryError> {
+    let topology = geom.topology;
+    let cores = topology.cores();
+    let usable_cores = cores - geom.reserved_cores;
+    let cores_per_region = match binding {
+        Binding::Compact => cores,
+        Binding::Scatter => topology.cores_per_domain,
+        Binding::Spread => topology.cores_per_domain * topology.domains_per_socket,
+    };
+    let regions = cores / cores_per_region;
+    let region_cpus: Vec<Vec<u32>> = (0..regions)
+        .map(|region| {
+            (region * cores_per_region..(region + 1) * cores_per_region)
+                .filter(|&core| core < usable_cores)
+                .flat_map(|core| (0..topology.smt).map(move |hw| hw * cores + core))
+                .collect()
+        })
+        .collect();
+    let tasks_per_region = tasks.div_ceil(regions);
+    (0..tasks)
+        .map(|task| {
+            let (region, index) = match binding {
+                Binding::Compact => (0, task),
+                Binding::Scatter => (task % regions, task / regions),
+                Binding::Spread => (task / tasks_per_region, task % tasks_per_region),
+            };
+            let first = (index * geom.threads) as usize;
+            let last = first + geom.threads as usize;
+            region_cpus[region as usize]
+                .get(first..last)
+                .map(|cpus| cpus.to_vec())
+                .ok_or_else(|| GeometryError {
+                    message: format!(
+                        "{} tasks of {} threads cannot be placed on a node with {} binding",
+                        tasks, geom.threads, binding
+                    ),
+                })
+        })
+        .collect()
+}
+
+/// The way a single node in a reservation is used.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct NodeUsage {
+    /// The number of tasks placed on the node.
+    pub tasks: u32,
+    /// The number of logical CPUs on the node that are active.
+    pub used_cpus: u32,
+    /// The number of logical CPUs on the node that are idle, excluding those
+    /// reserved for the system or intentionally left unpopulated.
+    pub idle_cpus: u32,
+}
+
+/// A run of consecutive nodes in a reservation that are all used the same way.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+pub struct NodeRun {
+    /// The number of nodes in the run.
+    pub count: u32,
+    /// The usage of each node in the run.
+    #[serde(flatten)]
+    pub node: NodeUsage,
+}
+
+// The number of tasks placed on each node of a reservation, in node order.
+fn node_task_counts(geom: Geometry) -> Vec<u32> {
+    node_task_runs(geom)
+        .into_iter()
+        .flat_map(|(count, tasks)| std::iter::repeat(tasks).take(count as usize))
+        .collect()
+}
+
+// The number of tasks placed on the nodes of a reservation, as runs of
+// consecutive nodes with the same number of tasks given as (nodes, tasks), in
+// node order. Some runs may be empty.
+fn node_task_runs(geom: Geometry) -> Vec<(u32, u32)> {
+    let tasks_per_node = geom.tasks_per_node();
+    let needed_nodes = geom.tasks.div_ceil(tasks_per_node);
+    let num_nodes = geom.nodes.unwrap_or(0).max(needed_nodes);
+    match geom.distribution {
+        Distribution::Packed => {
+            let full_nodes = geom.tasks / tasks_per_node;
+            let remainder = geom.tasks - (full_nodes * tasks_per_node);
+            let partial_nodes = u32::from(remainder > 0);
+            vec![
+                (full_nodes, tasks_per_node),
+                (partial_nodes, remainder),
+                (num_nodes - full_nodes - partial_nodes, 0),
+            ]
+        }
+        Distribution::Balanced | Distribution::Cyclic => {
+            let base = geom.tasks / num_nodes;
+            let extra = geom.tasks % num_nodes;
+            vec![(extra, base + 1), (num_nodes - extra, base)]
+        }
     }
 }
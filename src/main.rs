@@ -1,59 +1,260 @@
-use clap::{builder::PossibleValue, crate_version, value_parser, Parser, ValueEnum};
+use clap::{builder::PossibleValue, crate_version, value_parser, Parser, Subcommand, ValueEnum};
 
 mod config;
 mod report;
 mod searchopts;
 
-use crate::config::Config;
-use crate::searchopts::SearchOptions;
-use pestr::{Geometry, Reservation};
+use crate::config::{Config, SearchConfig, Settings, Source, SETTINGS};
+use crate::report::{AuditResult, CpuUse, Location, MachineResult, Setting, VariableStyle};
+use crate::searchopts::SEARCH_OPTIONS;
+use serde_json::json;
 
-static CONFIG_FILE_NAME: &str = ".pestr.toml";
+use pestr::{
+    allocation, jobscript, launcher, pbs, Binding, Distribution, Geometry, NodeTopology, Placement,
+    Reservation,
+};
 
 #[derive(Parser, Debug)]
 #[clap(version = crate_version!(), author = "Andrew Dawson <andrew.dawson@ecmwf.int>")]
 #[clap(about = "A PEs and threads calculator")]
+#[clap(subcommand_negates_reqs = true)]
+#[clap(override_usage = "pestr [OPTIONS] <PES> <THREADS>\n       pestr [OPTIONS] <COMMAND>")]
 struct Args {
     /// The number of physical CPUs per node on the target architecture
-    #[clap(short = 'n', long, value_parser=value_parser!(u32).range(1..))]
+    #[clap(short = 'n', long, global = true, value_parser=value_parser!(u32).range(1..))]
     cpus_per_node: Option<u32>,
 
-    /// Assume hyperthreading (doubles the effective CPUs per node)
-    #[clap(short = 'y', long)]
+    /// Assume hyperthreading (doubles the effective CPUs per node), this is
+    /// equivalent to --smt 2
+    #[clap(short = 'y', long, global = true, conflicts_with = "smt")]
     hyperthreading: bool,
 
-    /// Suggest alternative geometries that fill whole nodes, the option
-    /// can be specified on its own, or with an argument. On its own it
-    /// will perform a search within parameters from the configuration
-    /// file, or using default parameters if no configuration file exists.
-    /// If an argument is given it can be a comma-separated config values
-    /// where the following are allowed: pe_radius=FLOAT, thread_radius=FLOAT,
-    /// conserve_nodes.
-    ///
-    /// The values for pe_radius and thread_radius are floating point numbers
-    /// indicating the search radius allowed for PEs and threads respectively,
-    /// as a fraction of their given values. The conserve_nodes flag indicates
-    /// that you require all suggested geometries to use the same number of
-    /// nodes as the input geometry.
-    #[clap(short, long)]
+    /// The number of logical CPUs per physical CPU (the SMT level), for
+    /// example 4 on an SMT4 machine
+    #[clap(long, global = true, value_parser=value_parser!(u32).range(1..))]
+    smt: Option<u32>,
+
+    /// The number of physical CPUs per node reserved for the system (core
+    /// specialisation, e.g. Slurm's CoreSpecCount) that cannot be used by the job
+    #[clap(long, global = true)]
+    reserved_cores: Option<u32>,
+
+    /// The number of PEs to place on each node, use this to deliberately
+    /// underpopulate nodes (defaults to as many as will fit on a node)
+    #[clap(long, global = true, alias = "ntasks-per-node", value_parser=value_parser!(u32).range(1..))]
+    tasks_per_node: Option<u32>,
+
+    /// How PEs are distributed over nodes: 'packed' fills each node in turn,
+    /// 'balanced' spreads PEs evenly with consecutive PEs on the same node,
+    /// and 'cyclic' spreads PEs evenly in a round-robin fashion
+    #[clap(long, global = true)]
+    distribution: Option<Distribution>,
+
+    /// How the threads of each PE are bound to logical CPUs, used by the
+    /// placement and masks output formats: 'compact' packs PEs onto
+    /// consecutive CPUs, 'scatter' places consecutive PEs in different NUMA
+    /// domains, and 'spread' divides the PEs on a node evenly between sockets
+    #[clap(long, global = true, default_value_t = Binding::Compact)]
+    binding: Binding,
+
+    /// Comma-separated names of the hosts the job runs on, one per node, used
+    /// by the rankfile and hostfile output formats
+    #[clap(long, global = true, value_delimiter = ',')]
+    hosts: Vec<String>,
+
+    /// The number of CPU sockets per node, the physical CPUs are assumed to be
+    /// split evenly between sockets
+    #[clap(long, global = true, value_parser=value_parser!(u32).range(1..))]
+    sockets: Option<u32>,
+
+    /// The number of NUMA domains in each socket, the physical CPUs in a socket
+    /// are assumed to be split evenly between NUMA domains
+    #[clap(long, global = true, value_parser=value_parser!(u32).range(1..))]
+    domains_per_socket: Option<u32>,
+
+    /// Suggest alternative geometries that fill whole nodes
+    #[clap(short, long, global = true, long_help = searchopts::help())]
     search: Option<Option<String>>,
 
+    /// The OpenMP stack size used by the env output format
+    #[clap(long, global = true, default_value = "64M")]
+    omp_stacksize: String,
+
+    /// A prefix added to the names of the variables printed by the vars
+    /// output format, for example "PESTR_"
+    #[clap(long, global = true, default_value = "")]
+    var_prefix: String,
+
+    /// The syntax used by the vars output format: 'sh' for sh-like shells,
+    /// 'csh' for csh-like shells, or 'ecflow' for ecFlow edit statements
+    #[clap(value_enum, long, global = true, default_value_t = VariableStyle::Sh)]
+    var_style: VariableStyle,
+
     /// Output format selection
-    #[clap(value_enum, short, long, default_value_t=Reporter::Text)]
+    #[clap(value_enum, short, long, global = true, default_value_t=Reporter::Text)]
     report_format: Reporter,
 
+    /// The name of a machine defined in the configuration file, whose
+    /// settings are used in place of the top-level settings in the file, this
+    /// can also be set with the PESTR_MACHINE environment variable
+    #[clap(long, global = true)]
+    machine: Option<String>,
+
     /// Path to a configuration file.
     ///
     /// The file should be im TOML format and may contain a top-level key
     /// 'cpus_per_node' indicating the number of physical CPUs per node on
-    /// the target architecture, and a section 'search' that may contain
-    /// keys 'pe_radius', 'thread_radius' and 'conserve_nodes', see the
-    /// documentation for the --search option for details. Values given on
-    /// the command line will supercede those from the config file.
-    /// By default the configuration is expected in ~/.pestr.toml.
-    #[clap(short, long)]
+    /// the target architecture, a key 'smt' giving the number of logical
+    /// CPUs per physical CPU, a key 'reserved_cores' giving the number of
+    /// physical CPUs per node reserved for the system, a key 'distribution'
+    /// giving the default distribution of PEs over nodes, keys 'sockets' and
+    /// 'domains_per_socket' describing the layout of the node, and a section
+    /// 'search' that may contain any of the search options, see the
+    /// documentation for the --search option for details. Settings for
+    /// individual machines may be given in sections named 'machines.NAME'
+    /// containing any of these keys, and a top-level key 'default_machine'
+    /// names the machine used when none is selected. Values given on the
+    /// command line will supercede those from the config file.
+    /// By default the settings are merged from /etc/pestr.toml, the user's
+    /// ~/.config/pestr/pestr.toml and ~/.pestr.toml, and the nearest
+    /// .pestr.toml in the current directory or one of its parents, with later
+    /// files taking precedence, if this option is given only the named file
+    /// is read.
+    #[clap(short, long, global = true)]
     config_file: Option<String>,
 
+    #[clap(subcommand)]
+    command: Option<Command>,
+
+    /// Number of PEs (MPI tasks) allocated to the job
+    #[clap(required = true, value_parser=value_parser!(u32).range(1..))]
+    pes: Option<u32>,
+
+    /// Number of threads allocated to the job
+    #[clap(required = true, value_parser=value_parser!(u32).range(1..))]
+    threads: Option<u32>,
+}
+
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Find the node and logical CPUs a PE runs on, or the PE running on a
+    /// given logical CPU of a node
+    Locate(LocateArgs),
+
+    /// Check the geometry requested by a PBS select statement, for example
+    /// "select=4:ncpus=128:mpiprocs=32:ompthreads=4", the statement's ncpus
+    /// is taken as the number of logical CPUs per node
+    Select {
+        /// The select statement to check
+        statement: String,
+    },
+
+    /// Check the geometry of a launcher command line, for example
+    /// "srun -n 512 -c 16 --hint=nomultithread ./model", srun, aprun, mpiexec
+    /// and mpirun commands are understood
+    ParseCmd {
+        /// The launcher command line to check
+        command: String,
+    },
+
+    /// Check the geometry of the job running in the current Slurm or PBS
+    /// allocation against the allocation, using the environment variables set
+    /// by the scheduler, exits with a non-zero status if they do not match
+    CheckEnv,
+
+    /// Evaluate a geometry on every machine defined in the configuration
+    /// file, use --search to include the alternate geometry for each machine
+    /// that fills its nodes and is closest in size to the given geometry
+    Compare {
+        /// Number of PEs (MPI tasks) allocated to the job
+        #[clap(value_parser=value_parser!(u32).range(1..))]
+        pes: u32,
+
+        /// Number of threads allocated to the job
+        #[clap(value_parser=value_parser!(u32).range(1..))]
+        threads: u32,
+    },
+
+    /// Propose geometries on one machine that fill whole nodes and match a
+    /// geometry on another machine, the search radius is taken from --search
+    /// or the target machine's search settings
+    Translate {
+        /// The machine the geometry is for
+        #[clap(long)]
+        from: String,
+
+        /// The machine to propose geometries for
+        #[clap(long)]
+        to: String,
+
+        /// What to keep from the original geometry: 'cores' keeps exactly the
+        /// total number of CPU cores in use, 'threads' keeps the number of
+        /// threads per PE, and 'nodes' keeps the number of nodes
+        #[clap(value_enum, long, default_value_t = Keep::Cores)]
+        keep: Keep,
+
+        /// Number of PEs (MPI tasks) allocated to the job
+        #[clap(value_parser=value_parser!(u32).range(1..))]
+        pes: u32,
+
+        /// Number of threads allocated to the job
+        #[clap(value_parser=value_parser!(u32).range(1..))]
+        threads: u32,
+    },
+
+    /// Show the configuration in use, or write a template configuration file
+    Config {
+        #[clap(subcommand)]
+        command: ConfigCommand,
+    },
+
+    /// Check the geometries requested by Slurm or PBS batch scripts for idle
+    /// CPU cores, exits with a non-zero status if any reservation is not
+    /// filled or a script cannot be read
+    Audit {
+        /// The batch scripts to check
+        #[clap(required = true)]
+        files: Vec<String>,
+    },
+}
+
+#[derive(Subcommand, Debug)]
+enum ConfigCommand {
+    /// Show the value of every setting and where it came from: a
+    /// configuration file, an environment variable, a command line option,
+    /// or the built-in default
+    Show,
+
+    /// Write a configuration file with every setting commented out at its
+    /// default value
+    Init {
+        /// The file to write, defaults to ~/.pestr.toml
+        path: Option<String>,
+
+        /// Overwrite the file if it already exists
+        #[clap(long)]
+        force: bool,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct LocateArgs {
+    /// The PE (MPI rank) to locate, counting from 0
+    #[clap(long, required_unless_present = "node", conflicts_with = "node")]
+    rank: Option<u32>,
+
+    /// Only locate this thread of the PE, counting from 0
+    #[clap(long, requires = "rank")]
+    thread: Option<u32>,
+
+    /// The node to look up, counting from 0, used with --cpu
+    #[clap(long, requires = "cpu")]
+    node: Option<u32>,
+
+    /// The logical CPU on the node to look up, used with --node
+    #[clap(long, requires = "node")]
+    cpu: Option<u32>,
+
     /// Number of PEs (MPI tasks) allocated to the job
     #[clap(value_parser=value_parser!(u32).range(1..))]
     pes: u32,
@@ -63,77 +264,540 @@ struct Args {
     threads: u32,
 }
 
-fn main() -> Result<(), String> {
-    let args: Args = Args::parse();
-    let config_file = match args.config_file {
-        Some(config_file_path) => Some(shellexpand::tilde(&config_file_path).into_owned()),
-        None => match dirs::home_dir() {
-            Some(home) => home.join(CONFIG_FILE_NAME).to_str().map(|s| s.to_owned()),
-            None => None,
-        },
+fn main() {
+    if let Err(e) = run(Args::parse()) {
+        eprintln!("error: {}", e);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: Args) -> Result<(), String> {
+    let config_files = match &args.config_file {
+        Some(config_file_path) => vec![shellexpand::tilde(config_file_path).into_owned()],
+        None => config::config_files(),
     };
 
-    let config = match &config_file {
-        Some(c) => Config::from_file(c),
-        None => Config::new(),
+    // Writing a new configuration file must not depend on the existing ones,
+    // which may be the reason for writing it.
+    if let Some(Command::Config {
+        command: ConfigCommand::Init { path, force },
+    }) = &args.command
+    {
+        return init_config(path.as_deref(), *force);
+    }
+
+    let config = match Config::from_files(&config_files, args.machine.as_deref(), &cli(&args)) {
+        Ok(config) => config,
+        Err(e) => {
+            if let Some(Command::Config { .. }) = &args.command {
+                match args.report_format {
+                    Reporter::Json => report::config_error_json_reporter(&config_files, &e),
+                    _ => report::config_error_text_reporter(&config_files),
+                }
+            }
+            return Err(format!("{}", e));
+        }
     };
+    for warning in &config.warnings {
+        eprintln!("warning: {}", warning);
+    }
 
-    let cpus_per_node = args.cpus_per_node.unwrap_or(config.cpus_per_node);
+    match &args.command {
+        Some(Command::Locate(locate_args)) => locate(&args, &config, locate_args),
+        Some(Command::Select { statement }) => {
+            let geom = pbs::parse_select(statement, config.smt).map_err(|e| format!("{}", e))?;
+            calculate(&args, config, geom)
+        }
+        Some(Command::ParseCmd { command }) => {
+            let geom = launcher::parse_command(command, topology(&config)?)
+                .map_err(|e| format!("{}", e))?;
+            calculate(&args, config, geom)
+        }
+        Some(Command::CheckEnv) => check_env(&args, &config),
+        Some(Command::Compare { pes, threads }) => compare(&args, &config_files, *pes, *threads),
+        Some(Command::Translate {
+            from,
+            to,
+            keep,
+            pes,
+            threads,
+        }) => translate(&args, &config_files, (from, to), *keep, *pes, *threads),
+        Some(Command::Config { command }) => match command {
+            ConfigCommand::Show => show_config(&args, &config, &config_files),
+            ConfigCommand::Init { .. } => unreachable!(),
+        },
+        Some(Command::Audit { files }) => audit(&args, &config, files),
+        None => {
+            let geom = geometry(&args, &config, args.pes.unwrap(), args.threads.unwrap())?;
+            calculate(&args, config, geom)
+        }
+    }
+}
 
-    // Construct the Geometry representing the user's job, and compute its reservation.
-    let geom = Geometry::new(cpus_per_node, args.hyperthreading, args.pes, args.threads)
-        .map_err(|e| format!("{}", e))?;
+// Construct the Geometry representing the user's job from the command line
+// arguments and configuration.
+fn geometry(args: &Args, config: &Config, pes: u32, threads: u32) -> Result<Geometry, String> {
+    Geometry::from_topology(topology(config)?, pes, threads)
+        .and_then(|g| g.with_reserved_cores(config.reserved_cores))
+        .and_then(|g| match args.tasks_per_node {
+            Some(n) => g.with_tasks_per_node(n),
+            None => Ok(g),
+        })
+        .map(|g| g.with_distribution(config.distribution))
+        .map_err(|e| format!("{}", e))
+}
 
+// The layout of the CPUs within a node, from the configuration.
+fn topology(config: &Config) -> Result<NodeTopology, String> {
+    NodeTopology::with_cores(
+        config.cpus_per_node,
+        config.sockets,
+        config.domains_per_socket,
+        config.smt,
+    )
+    .map_err(|e| format!("{}", e))
+}
+
+// The settings given on the command line, which take precedence over the
+// environment and configuration files.
+fn cli(args: &Args) -> Settings {
+    let mut settings = Settings::default();
+    settings.cpus_per_node = args.cpus_per_node;
+    settings.smt = if args.hyperthreading {
+        Some(2)
+    } else {
+        args.smt
+    };
+    settings.reserved_cores = args.reserved_cores;
+    settings.distribution = args.distribution;
+    settings.sockets = args.sockets;
+    settings.domains_per_socket = args.domains_per_socket;
+    settings
+}
+
+// Report the reservation for the user's job, and optionally search for
+// alternate geometries.
+fn calculate(args: &Args, config: Config, geom: Geometry) -> Result<(), String> {
     let res = Reservation::from_geometry(geom);
 
-    // Determine alternate geometries that yield a full reservation, within the
-    // specified parameters. Use an empty list if the user didn't ask for
-    // alternate geometries.
-    let alternates = match args.search {
+    let alternates = alternates(args, config.search.clone(), geom)?;
+
+    match args.report_format {
+        Reporter::Text => report::text_reporter(config.machine.as_deref(), res, alternates),
+        Reporter::Json => report::json_reporter(config.machine.as_deref(), geom, res, alternates),
+        Reporter::Placement => report::placement_reporter(&placement(geom, args.binding)?),
+        Reporter::Masks => report::masks_reporter(&placement(geom, args.binding)?),
+        Reporter::Slurm => report::slurm_reporter(geom, res, alternates),
+        Reporter::Pbs => report::pbs_reporter(geom, res, alternates),
+        Reporter::Aprun => report::aprun_reporter(geom, res, alternates),
+        Reporter::Pals => report::pals_reporter(geom, res, alternates),
+        Reporter::Openmpi => report::openmpi_reporter(geom, res, alternates),
+        Reporter::Intelmpi => report::intelmpi_reporter(geom, res, alternates),
+        Reporter::Env => report::env_reporter(geom, res, alternates, &args.omp_stacksize),
+        Reporter::Rankfile => {
+            report::rankfile_reporter(&placement(geom, args.binding)?, hosts(args, res)?)
+        }
+        Reporter::Hostfile => report::hostfile_reporter(geom, hosts(args, res)?),
+        Reporter::Vars => {
+            report::vars_reporter(geom, res, alternates, &args.var_prefix, args.var_style)
+        }
+    }
+    Ok(())
+}
+
+// Determine alternate geometries that yield a full reservation, within the
+// specified parameters. Use an empty list if the user didn't ask for
+// alternate geometries.
+fn alternates(
+    args: &Args,
+    search: SearchConfig,
+    geom: Geometry,
+) -> Result<Vec<(Geometry, Reservation)>, String> {
+    Ok(match &args.search {
         None => Vec::new(),
         Some(search_option_str) => {
             let search_options = match search_option_str {
-                None => SearchOptions::default(config.search), // FIXME: here we need to inject from our config
-                Some(s) => SearchOptions::parse(&s, config.search)?, // FIXME: also here might need to know
+                None => search,
+                Some(s) => searchopts::apply(s, search)?,
             };
+            let geometries = search_options.alternates(geom, &|_, _| true)?;
+            search_options.limit(geom.tasks * geom.threads, geometries)
+        }
+    })
+}
 
-            let gr_filter = |_, r: Reservation| -> bool {
-                if search_options.conserve_nodes {
-                    r.nodes == res.nodes
-                } else {
-                    true
-                }
+// Evaluate a geometry on every machine defined in the configuration file,
+// with the alternate geometry closest to it in size if a search is requested.
+fn compare(args: &Args, config_files: &[String], pes: u32, threads: u32) -> Result<(), String> {
+    if config_files.is_empty() {
+        return Err(String::from("no configuration file to read machines from"));
+    }
+    let machines = config::machine_names(config_files).map_err(|e| format!("{}", e))?;
+    if machines.is_empty() {
+        return Err(format!(
+            "no machines are defined in {}",
+            config_files.join(", ")
+        ));
+    }
+    let mut results = Vec::new();
+    for name in machines {
+        let config = Config::from_files(config_files, Some(&name), &cli(args))
+            .map_err(|e| format!("{}", e))?;
+        let geom = geometry(args, &config, pes, threads)
+            .map_err(|e| format!("machine '{}': {}", name, e))?;
+        let res = Reservation::from_geometry(geom);
+        let cpus = geom.tasks * geom.threads;
+        let best = alternates(args, config.search, geom)?
+            .into_iter()
+            .min_by_key(|(g, r)| ((g.tasks * g.threads).abs_diff(cpus), r.nodes));
+        results.push(MachineResult {
+            name,
+            geom,
+            res,
+            best,
+        });
+    }
+    match args.report_format {
+        Reporter::Text => report::compare_text_reporter(&results),
+        Reporter::Json => report::compare_json_reporter(&results),
+        _ => return Err(String::from("compare only supports text and json output")),
+    }
+    Ok(())
+}
+
+// Propose geometries on one machine that fill whole nodes and keep part of
+// a geometry on another machine.
+fn translate(
+    args: &Args,
+    config_files: &[String],
+    (from, to): (&str, &str),
+    keep: Keep,
+    pes: u32,
+    threads: u32,
+) -> Result<(), String> {
+    if config_files.is_empty() {
+        return Err(String::from("no configuration file to read machines from"));
+    }
+    let from_config =
+        Config::from_files(config_files, Some(from), &cli(args)).map_err(|e| format!("{}", e))?;
+    let to_config =
+        Config::from_files(config_files, Some(to), &cli(args)).map_err(|e| format!("{}", e))?;
+    let from_geom = geometry(args, &from_config, pes, threads)
+        .map_err(|e| format!("machine '{}': {}", from, e))?;
+    let from_res = Reservation::from_geometry(from_geom);
+
+    // Search around the geometry that would use the same number of nodes on
+    // the target machine when keeping nodes, otherwise around the original.
+    let to_pes = match keep {
+        Keep::Nodes => {
+            let to_cpus = geometry(args, &to_config, 1, 1)?.usable_cpus();
+            (pes as u64 * to_cpus as u64 / from_geom.usable_cpus() as u64).max(1) as u32
+        }
+        Keep::Cores | Keep::Threads => pes,
+    };
+    let to_geom = geometry(args, &to_config, to_pes, threads)
+        .map_err(|e| format!("machine '{}': {}", to, e))?;
+    let search_options = match &args.search {
+        Some(Some(s)) => searchopts::apply(s, to_config.search.clone())?,
+        _ => to_config.search.clone(),
+    };
+    let cpus = from_geom.tasks * from_geom.threads;
+    let filter = |g: Geometry, r: Reservation| match keep {
+        Keep::Cores => g.tasks * g.threads == cpus,
+        Keep::Threads => g.threads == from_geom.threads,
+        Keep::Nodes => r.nodes == from_res.nodes,
+    };
+    let mut geometries = search_options.alternates(to_geom, &filter)?;
+    geometries.sort_by_key(|(g, r)| ((g.tasks * g.threads).abs_diff(cpus), r.nodes));
+    let geometries = search_options.limit(cpus, geometries);
+
+    let source = MachineResult {
+        name: from.to_owned(),
+        geom: from_geom,
+        res: from_res,
+        best: None,
+    };
+    match args.report_format {
+        Reporter::Text => report::translate_text_reporter(&source, to, geometries),
+        Reporter::Json => report::translate_json_reporter(&source, to, geometries),
+        _ => return Err(String::from("translate only supports text and json output")),
+    }
+    Ok(())
+}
+
+// Look up where a PE runs, or which PE runs on a CPU.
+fn locate(args: &Args, config: &Config, locate_args: &LocateArgs) -> Result<(), String> {
+    let geom = geometry(args, config, locate_args.pes, locate_args.threads)?;
+    let placement = placement(geom, args.binding)?;
+    let location = match (locate_args.rank, locate_args.node, locate_args.cpu) {
+        (Some(rank), _, _) => match locate_args.thread {
+            Some(thread) => placement
+                .thread(rank, thread)
+                .map(Location::Thread)
+                .ok_or_else(|| format!("PE {} does not have a thread {}", rank, thread))?,
+            None => placement
+                .rank(rank)
+                .map(Location::Rank)
+                .ok_or_else(|| format!("the job does not have a PE {}", rank))?,
+        },
+        (None, Some(node), Some(cpu)) => {
+            let res = Reservation::from_geometry(geom);
+            if node >= res.nodes {
+                return Err(format!("the job does not have a node {}", node));
+            }
+            let logical_cpus = geom.topology().logical_cpus();
+            if cpu >= logical_cpus {
+                return Err(format!(
+                    "node {} does not have a CPU {}, it has {} logical CPUs",
+                    node, cpu, logical_cpus
+                ));
+            }
+            let usage = match placement.rank_at(node, cpu) {
+                Some(thread) => CpuUse::Thread(thread),
+                None if placement.is_reserved(cpu) => CpuUse::Reserved,
+                None => CpuUse::Idle,
             };
-            geom.alternates(
-                search_options.pe_radius,
-                search_options.thread_radius,
-                &gr_filter,
-            )
+            Location::Cpu(node, cpu, usage)
         }
+        _ => unreachable!(),
     };
+    match args.report_format {
+        Reporter::Text => report::locate_text_reporter(location),
+        Reporter::Json => report::locate_json_reporter(location),
+        _ => return Err(String::from("locate only supports text and json output")),
+    }
+    Ok(())
+}
 
+// Check the job running in the current allocation against the allocation.
+fn check_env(args: &Args, config: &Config) -> Result<(), String> {
+    let check = allocation::check_allocation(|name| std::env::var(name).ok(), topology(config)?)
+        .map_err(|e| format!("{}", e))?;
     match args.report_format {
-        Reporter::Text => report::text_reporter(res, alternates),
-        Reporter::Json => report::json_reporter(geom, res, alternates),
+        Reporter::Text => report::allocation_text_reporter(&check),
+        Reporter::Json => report::allocation_json_reporter(&check),
+        _ => return Err(String::from("check-env only supports text and json output")),
+    }
+    if !check.mismatches.is_empty() {
+        return Err(String::from("the job does not match its allocation"));
     }
     Ok(())
 }
 
+// Show the value of every setting and where it came from.
+fn show_config(args: &Args, config: &Config, config_files: &[String]) -> Result<(), String> {
+    let search_flags = match &args.search {
+        Some(Some(s)) => searchopts::parse(s)?,
+        _ => Vec::new(),
+    };
+    let setting = |key: &str, value, flag: Option<&'static str>| Setting {
+        key: key.to_owned(),
+        value,
+        source: match flag {
+            Some(flag) => Source::Cli(flag),
+            None => config.sources[key].clone(),
+        },
+    };
+    let mut settings = vec![setting("machine", json!(config.machine), None)];
+    for config_setting in SETTINGS {
+        settings.push(setting(
+            config_setting.name,
+            (config_setting.get)(config),
+            None,
+        ));
+    }
+    for option in SEARCH_OPTIONS {
+        let setting = match search_flags
+            .iter()
+            .rev()
+            .find(|(o, _)| o.name == option.name)
+        {
+            Some((_, value)) => setting(&option.key(), value.to_json(), Some("--search")),
+            None => setting(&option.key(), option.get(&config.search).to_json(), None),
+        };
+        settings.push(setting);
+    }
+    match args.report_format {
+        Reporter::Text => report::config_text_reporter(config_files, &settings),
+        Reporter::Json => report::config_json_reporter(config_files, &settings),
+        _ => {
+            return Err(String::from(
+                "config show only supports text and json output",
+            ))
+        }
+    }
+    Ok(())
+}
+
+// Write a template configuration file.
+fn init_config(path: Option<&str>, force: bool) -> Result<(), String> {
+    let path = match path {
+        Some(path) => shellexpand::tilde(path).into_owned(),
+        None => config::home_config_file()
+            .ok_or("cannot find your home directory, give the file to write")?,
+    };
+    if !force && std::path::Path::new(&path).exists() {
+        return Err(format!(
+            "{} already exists, use --force to overwrite it",
+            path
+        ));
+    }
+    std::fs::write(&path, config::template())
+        .map_err(|e| format!("cannot write {}, {}", path, e))?;
+    println!("wrote a configuration template to {}", path);
+    Ok(())
+}
+
+// Check the jobs in batch scripts for idle CPU cores.
+fn audit(args: &Args, config: &Config, files: &[String]) -> Result<(), String> {
+    let topology = topology(config)?;
+    let mut results = Vec::new();
+    for file in files {
+        let jobs = std::fs::read_to_string(file)
+            .map_err(|e| format!("{}", e))
+            .and_then(|script| {
+                jobscript::parse_script(&script, topology).map_err(|e| format!("{}", e))
+            });
+        match jobs {
+            Ok(jobs) if jobs.is_empty() => results.push(AuditResult::Error(
+                file.clone(),
+                String::from("no job geometry found"),
+            )),
+            Ok(jobs) => results.extend(
+                jobs.into_iter()
+                    .map(|job| AuditResult::Job(file.clone(), job)),
+            ),
+            Err(message) => results.push(AuditResult::Error(file.clone(), message)),
+        }
+    }
+    let failures = results
+        .iter()
+        .filter(|result| match result {
+            AuditResult::Job(_, job) => !Reservation::from_geometry(job.geometry).is_filled(),
+            AuditResult::Error(_, _) => true,
+        })
+        .count();
+    match args.report_format {
+        Reporter::Text => report::audit_text_reporter(&results),
+        Reporter::Json => report::audit_json_reporter(&results),
+        _ => return Err(String::from("audit only supports text and json output")),
+    }
+    if failures > 0 {
+        return Err(format!(
+            "{} of {} jobs do not fill their reservation or could not be read",
+            failures,
+            results.len()
+        ));
+    }
+    Ok(())
+}
+
+// The names of the hosts the job runs on, there must be at least one per node.
+fn hosts(args: &Args, res: Reservation) -> Result<&[String], String> {
+    if args.hosts.len() < res.nodes as usize {
+        Err(format!(
+            "the job needs {} nodes but {} host names were given with --hosts",
+            res.nodes,
+            args.hosts.len()
+        ))
+    } else {
+        Ok(&args.hosts)
+    }
+}
+
+fn placement(geom: Geometry, binding: Binding) -> Result<Placement, String> {
+    Placement::new(geom, binding).map_err(|e| format!("{}", e))
+}
+
 #[derive(Copy, Clone, Debug, PartialEq, Eq, PartialOrd, Ord)]
 enum Reporter {
+    Aprun,
+    Env,
+    Hostfile,
+    Intelmpi,
     Json,
+    Masks,
+    Openmpi,
+    Pals,
+    Pbs,
+    Placement,
+    Rankfile,
+    Slurm,
     Text,
+    Vars,
 }
 
 impl ValueEnum for Reporter {
     fn value_variants<'a>() -> &'a [Self] {
-        &[Self::Json, Self::Text]
+        &[
+            Self::Aprun,
+            Self::Env,
+            Self::Hostfile,
+            Self::Intelmpi,
+            Self::Json,
+            Self::Masks,
+            Self::Openmpi,
+            Self::Pals,
+            Self::Pbs,
+            Self::Placement,
+            Self::Rankfile,
+            Self::Slurm,
+            Self::Text,
+            Self::Vars,
+        ]
     }
 
     fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
         Some(match self {
+            Self::Aprun => PossibleValue::new("aprun"),
+            Self::Env => PossibleValue::new("env"),
+            Self::Hostfile => PossibleValue::new("hostfile"),
+            Self::Intelmpi => PossibleValue::new("intelmpi"),
             Self::Json => PossibleValue::new("json"),
+            Self::Masks => PossibleValue::new("masks"),
+            Self::Openmpi => PossibleValue::new("openmpi"),
+            Self::Pals => PossibleValue::new("pals"),
+            Self::Pbs => PossibleValue::new("pbs"),
+            Self::Placement => PossibleValue::new("placement"),
+            Self::Rankfile => PossibleValue::new("rankfile"),
+            Self::Slurm => PossibleValue::new("slurm"),
             Self::Text => PossibleValue::new("text"),
+            Self::Vars => PossibleValue::new("vars"),
+        })
+    }
+}
+
+impl ValueEnum for VariableStyle {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Csh, Self::Ecflow, Self::Sh]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(match self {
+            Self::Csh => PossibleValue::new("csh"),
+            Self::Ecflow => PossibleValue::new("ecflow"),
+            Self::Sh => PossibleValue::new("sh"),
+        })
+    }
+}
+
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+enum Keep {
+    Cores,
+    Nodes,
+    Threads,
+}
+
+impl ValueEnum for Keep {
+    fn value_variants<'a>() -> &'a [Self] {
+        &[Self::Cores, Self::Nodes, Self::Threads]
+    }
+
+    fn to_possible_value(&self) -> Option<clap::builder::PossibleValue> {
+        Some(match self {
+            Self::Cores => PossibleValue::new("cores"),
+            Self::Nodes => PossibleValue::new("nodes"),
+            Self::Threads => PossibleValue::new("threads"),
         })
     }
 }
@@ -0,0 +1,159 @@
+//! Conversion between job geometries and PBS Pro / Torque select statements.
+use crate::{Geometry, ParseError, Reservation};
+
+/// Returns the select statement requesting the nodes needed by a geometry,
+/// in the form `select=N:ncpus=C:mpiprocs=M:ompthreads=T`.
+///
+/// Whole nodes are requested, so `ncpus` is the number of usable logical CPUs
+/// per node. Nodes with different numbers of tasks are requested as separate
+/// chunks joined with `+`.
+///
+/// # Examples
+/// ```
+/// use pestr::{pbs, Geometry};
+/// let geom = Geometry::new(128, 1, 128, 12).unwrap();
+/// assert_eq!(
+///     pbs::select_statement(geom),
+///     "select=12:ncpus=128:mpiprocs=10:ompthreads=12+1:ncpus=128:mpiprocs=8:ompthreads=12"
+/// );
+/// ```
+pub fn select_statement(geom: Geometry) -> String {
+    let chunks: Vec<String> = Reservation::node_runs(geom)
+        .iter()
+        .map(|run| {
+            format!(
+                "{}:ncpus={}:mpiprocs={}:ompthreads={}",
+                run.count,
+                geom.usable_cpus(),
+                run.node.tasks,
+                geom.threads
+            )
+        })
+        .collect();
+    format!("select={}", chunks.join("+"))
+}
+
+/// Parse a select statement into a geometry, or return an error if the
+/// statement is malformed or does not describe a single job geometry.
+///
+/// The statement may optionally be preceded by `-l`. Each chunk's `ncpus`
+/// is taken as the number of logical CPUs per node, and all chunks must have
+/// the same `ncpus` and `ompthreads`. When not given, `mpiprocs` defaults to 1
+/// and `ompthreads` defaults to `ncpus / mpiprocs`. Resources other than
+/// these, such as `mem`, are ignored.
+///
+/// # Arguments
+///
+/// * `statement` - The select statement to parse.
+/// * `smt` - The number of logical CPUs per physical core on the nodes.
+///
+/// # Examples
+/// ```
+/// use pestr::{pbs, Reservation};
+/// let geom = pbs::parse_select("select=4:ncpus=128:mpiprocs=10:ompthreads=12", 1).unwrap();
+/// assert_eq!((geom.tasks, geom.threads), (40, 12));
+/// assert_eq!(Reservation::from_geometry(geom).idle_cpus, 32);
+/// assert!(pbs::parse_select("select=4000000000:ncpus=128:mpiprocs=2", 1).is_err());
+/// ```
+pub fn parse_select(statement: &str, smt: u32) -> Result<Geometry, ParseError> {
+    let statement = statement.trim();
+    let statement = statement.strip_prefix("-l").unwrap_or(statement).trim();
+    let chunks = statement
+        .strip_prefix("select=")
+        .ok_or_else(|| ParseError {
+            message: format!("'{}' is not a select statement", statement),
+        })?
+        .split('+')
+        .map(parse_chunk)
+        .collect::<Result<Vec<Chunk>, ParseError>>()?;
+    let first = chunks[0];
+    if chunks
+        .iter()
+        .any(|c| c.ncpus != first.ncpus || c.ompthreads != first.ompthreads)
+    {
+        return Err(ParseError {
+            message: String::from("all chunks must have the same ncpus and ompthreads"),
+        });
+    }
+    if first.ncpus % smt != 0 {
+        return Err(ParseError {
+            message: format!(
+                "ncpus={} is not a multiple of the SMT level {}",
+                first.ncpus, smt
+            ),
+        });
+    }
+    let tasks = chunks
+        .iter()
+        .try_fold(0u32, |tasks, c| {
+            c.count.checked_mul(c.mpiprocs)?.checked_add(tasks)
+        })
+        .ok_or_else(|| ParseError {
+            message: format!("'{}' asks for too many PEs", statement),
+        })?;
+    let tasks_per_node = chunks.iter().map(|c| c.mpiprocs).max().unwrap();
+    let geom = Geometry::new(first.ncpus / smt, smt, tasks, first.ompthreads)?;
+    if tasks_per_node == geom.tasks_per_node() {
+        Ok(geom)
+    } else {
+        Ok(geom.with_tasks_per_node(tasks_per_node)?)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct Chunk {
+    count: u32,
+    ncpus: u32,
+    mpiprocs: u32,
+    ompthreads: u32,
+}
+
+fn parse_chunk(chunk: &str) -> Result<Chunk, ParseError> {
+    fn parse_value(chunk: &str, text: &str) -> Result<u32, ParseError> {
+        match text.parse() {
+            Ok(value) if value > 0 => Ok(value),
+            _ => Err(ParseError {
+                message: format!("invalid value '{}' in chunk '{}'", text, chunk),
+            }),
+        }
+    }
+    let mut parts = chunk.split(':');
+    let mut count = 1;
+    let mut ncpus = None;
+    let mut mpiprocs = None;
+    let mut ompthreads = None;
+    if let Some(first) = parts.next() {
+        if !first.contains('=') {
+            count = parse_value(chunk, first)?;
+        } else {
+            parts = chunk.split(':');
+        }
+    }
+    for part in parts {
+        match part.split_once('=') {
+            Some(("ncpus", value)) => ncpus = Some(parse_value(chunk, value)?),
+            Some(("mpiprocs", value)) => mpiprocs = Some(parse_value(chunk, value)?),
+            Some(("ompthreads", value)) => ompthreads = Some(parse_value(chunk, value)?),
+            Some(_) => {}
+            None => {
+                return Err(ParseError {
+                    message: format!(
+                        "expected resource=value in chunk '{}', got '{}'",
+                        chunk, part
+                    ),
+                })
+            }
+        }
+    }
+    let ncpus = ncpus.ok_or_else(|| ParseError {
+        message: format!("chunk '{}' does not give ncpus", chunk),
+    })?;
+    let mpiprocs = mpiprocs.unwrap_or(1);
+    let ompthreads = ompthreads.unwrap_or_else(|| (ncpus / mpiprocs).max(1));
+    Ok(Chunk {
+        count,
+        ncpus,
+        mpiprocs,
+        ompthreads,
+    })
+}
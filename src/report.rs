@@ -1,15 +1,75 @@
 use serde_json::json;
 
-use pestr::{Geometry, Reservation};
+use crate::config::{ConfigError, Source};
+use pestr::allocation::AllocationCheck;
+use pestr::jobscript::ScriptJob;
+use pestr::{
+    pbs, Distribution, Geometry, NodeTopology, Placement, RankPlacement, Reservation,
+    ThreadLocation,
+};
+
+// The result of looking up where a PE runs, or what runs on a CPU
+pub enum Location {
+    Rank(RankPlacement),
+    Thread(ThreadLocation),
+    Cpu(u32, u32, CpuUse),
+}
+
+// What a logical CPU on a node is used for
+pub enum CpuUse {
+    Thread(ThreadLocation),
+    Reserved,
+    Idle,
+}
+
+// The result of auditing a batch script, either a job found in the script
+// or an error reading the script
+pub enum AuditResult {
+    Job(String, ScriptJob),
+    Error(String, String),
+}
+
+// The syntax used to set variables
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum VariableStyle {
+    Csh,
+    Ecflow,
+    Sh,
+}
+
+// The effective value of a configuration setting and where it came from
+pub struct Setting {
+    pub key: String,
+    pub value: serde_json::Value,
+    pub source: Source,
+}
+
+// The result of evaluating a geometry on a machine, with the best alternate
+// geometry for the machine if a search was made
+pub struct MachineResult {
+    pub name: String,
+    pub geom: Geometry,
+    pub res: Reservation,
+    pub best: Option<(Geometry, Reservation)>,
+}
 
 // Reporting in JSON format
-pub fn json_reporter(geom: Geometry, res: Reservation, alternates: Vec<(Geometry, Reservation)>) {
+pub fn json_reporter(
+    machine: Option<&str>,
+    geom: Geometry,
+    res: Reservation,
+    alternates: Vec<(Geometry, Reservation)>,
+) {
     fn jsonize_job(geom: Geometry, res: Reservation) -> serde_json::Value {
         json!({"geometry": geom, "reservation": res})
     }
     let report = json!({
+        "machine": machine,
         "geometry": geom,
         "reservation": res,
+        "nodes": Reservation::node_runs(geom),
+        "load_imbalance": res.imbalance(),
+        "warnings": warnings(res),
         "alternatives": alternates
                         .iter()
                         .map(|&(g, r)| jsonize_job(g, r))
@@ -19,16 +79,38 @@ pub fn json_reporter(geom: Geometry, res: Reservation, alternates: Vec<(Geometry
 }
 
 // Reporting in human-readable plain text
-pub fn text_reporter(res: Reservation, alternates: Vec<(Geometry, Reservation)>) {
+pub fn text_reporter(
+    machine: Option<&str>,
+    res: Reservation,
+    alternates: Vec<(Geometry, Reservation)>,
+) {
     fn print_reservation(res: Reservation) {
         println!("{} nodes ({} CPU cores)", res.nodes, res.cpus);
-        if res.used_cpus != res.cpus {
+        if res.reserved_cpus > 0 {
+            println!("  {} CPU cores reserved for the system", res.reserved_cpus);
+        }
+        if res.underpopulated_cpus > 0 {
+            println!(
+                "  {} CPU cores intentionally unpopulated",
+                res.underpopulated_cpus
+            );
+        }
+        if !res.is_filled() {
             println!("warning: reservation is not filled");
             println!("  {} CPU cores in use", res.used_cpus);
             println!(
                 "  {} CPU cores idle across {} nodes",
                 res.idle_cpus, res.partial_nodes
             );
+            if res.imbalance() > 0 {
+                println!(
+                    "  load imbalance: {} to {} tasks per node",
+                    res.min_node_tasks, res.max_node_tasks
+                );
+            }
+        }
+        for warning in crossing_warnings(res) {
+            println!("warning: {}", warning);
         }
     }
 
@@ -39,6 +121,9 @@ pub fn text_reporter(res: Reservation, alternates: Vec<(Geometry, Reservation)>)
         );
     }
 
+    if let Some(machine) = machine {
+        println!("machine: {}", machine);
+    }
     print_reservation(res);
     if !alternates.is_empty() {
         println!("alternate geometries that fill the reservation:");
@@ -47,3 +132,841 @@ pub fn text_reporter(res: Reservation, alternates: Vec<(Geometry, Reservation)>)
         }
     }
 }
+
+// Reporting as a Slurm batch script header and srun command line, with any
+// alternate geometries included as commented out directives
+pub fn slurm_reporter(geom: Geometry, res: Reservation, alternates: Vec<(Geometry, Reservation)>) {
+    for warning in warnings(res) {
+        println!("# warning: {}", warning);
+    }
+    for option in slurm_job_options(geom, res) {
+        println!("#SBATCH {}", option);
+    }
+    println!("srun {}", slurm_step_options(geom, res).join(" "));
+    for (i, (g, r)) in alternates.into_iter().enumerate() {
+        println!();
+        println!(
+            "# alternate {}: {} x {} ({} nodes; {} CPU cores)",
+            i + 1,
+            g.tasks,
+            g.threads,
+            r.nodes,
+            r.cpus
+        );
+        for option in slurm_job_options(g, r) {
+            println!("##SBATCH {}", option);
+        }
+        println!("# srun {}", slurm_step_options(g, r).join(" "));
+    }
+}
+
+// Reporting as PBS resource directives, with any alternate geometries
+// included as commented out directives
+pub fn pbs_reporter(geom: Geometry, res: Reservation, alternates: Vec<(Geometry, Reservation)>) {
+    for warning in warnings(res) {
+        println!("# warning: {}", warning);
+    }
+    println!("#PBS -l {}", pbs::select_statement(geom));
+    println!("#PBS -l place=scatter:excl");
+    for (i, (g, r)) in alternates.into_iter().enumerate() {
+        println!();
+        println!(
+            "# alternate {}: {} x {} ({} nodes; {} CPU cores)",
+            i + 1,
+            g.tasks,
+            g.threads,
+            r.nodes,
+            r.cpus
+        );
+        println!("##PBS -l {}", pbs::select_statement(g));
+    }
+}
+
+// Reporting as a Cray ALPS aprun command line, with any alternate geometries
+// included as commented out commands
+pub fn aprun_reporter(geom: Geometry, res: Reservation, alternates: Vec<(Geometry, Reservation)>) {
+    launcher_reporter(
+        geom,
+        aprun_reservation(geom, res),
+        alternates,
+        &aprun_command,
+    );
+}
+
+// Reporting as an HPE PALS mpiexec command line, with any alternate geometries
+// included as commented out commands
+pub fn pals_reporter(geom: Geometry, res: Reservation, alternates: Vec<(Geometry, Reservation)>) {
+    launcher_reporter(geom, res, alternates, &pals_command);
+}
+
+// Reporting as a launcher command line, preceded by warnings about anything
+// in the geometry the launcher cannot express
+fn launcher_reporter(
+    geom: Geometry,
+    res: Reservation,
+    alternates: Vec<(Geometry, Reservation)>,
+    command: &dyn Fn(Geometry, Reservation) -> (String, Vec<String>),
+) {
+    let (line, compatibility) = command(geom, res);
+    for warning in warnings(res).into_iter().chain(compatibility) {
+        println!("# warning: {}", warning);
+    }
+    println!("{}", line);
+    for (i, (g, r)) in alternates.into_iter().enumerate() {
+        println!();
+        println!(
+            "# alternate {}: {} x {} ({} nodes; {} CPU cores)",
+            i + 1,
+            g.tasks,
+            g.threads,
+            r.nodes,
+            r.cpus
+        );
+        for line in command(g, r).0.lines() {
+            println!("# {}", line);
+        }
+    }
+}
+
+// An aprun command line for a geometry, and any ways in which it differs from
+// the geometry
+fn aprun_command(geom: Geometry, res: Reservation) -> (String, Vec<String>) {
+    let mut warnings = launcher_distribution_warnings("aprun", geom, res);
+    let tasks_per_node = res.max_node_tasks;
+    let mut options = vec![
+        format!("-n {}", geom.tasks),
+        format!("-N {}", tasks_per_node),
+        format!("-d {}", geom.threads),
+        format!("-j {}", geom.smt()),
+    ];
+    match aprun_pes_per_socket(geom, res) {
+        Ok(Some(pes_per_socket)) => options.push(format!("-S {}", pes_per_socket)),
+        Ok(None) => {}
+        Err(warning) => warnings.push(warning),
+    }
+    if geom.reserved_cores() > 0 {
+        options.push(format!("-r {}", geom.reserved_cores()));
+    }
+    options.push(String::from("-cc depth"));
+    (format!("aprun {}", options.join(" ")), warnings)
+}
+
+// The number of PEs aprun is told to place on each socket with -S, `None` on
+// a node with a single socket, or the reason -S cannot be used
+fn aprun_pes_per_socket(geom: Geometry, res: Reservation) -> Result<Option<u32>, String> {
+    let tasks_per_node = res.max_node_tasks;
+    let sockets = geom.topology().sockets;
+    if sockets == 1 {
+        Ok(None)
+    } else if tasks_per_node % sockets != 0 {
+        Err(format!(
+            "{} PEs per node cannot be split evenly over {} sockets, -S is not used",
+            tasks_per_node, sockets
+        ))
+    } else if tasks_per_node / sockets * geom.threads > geom.topology().cpus_per_socket() {
+        Err(format!(
+            "{} PEs of {} threads will not fit in a socket, -S is not used",
+            tasks_per_node / sockets,
+            geom.threads
+        ))
+    } else {
+        Ok(Some(tasks_per_node / sockets))
+    }
+}
+
+// The reservation for a geometry as aprun lays it out. With -S each socket is
+// filled like a node of its own, so no PE is split across sockets, and only
+// the NUMA domain crossings within a socket remain
+fn aprun_reservation(geom: Geometry, mut res: Reservation) -> Reservation {
+    if let Ok(Some(pes_per_socket)) = aprun_pes_per_socket(geom, res) {
+        let socket = NodeTopology {
+            sockets: 1,
+            ..geom.topology()
+        };
+        let socket_geom = Geometry::from_topology(socket, geom.tasks, geom.threads)
+            .and_then(|g| g.with_tasks_per_node(pes_per_socket));
+        if let Ok(socket_geom) = socket_geom {
+            res.domain_crossings = Reservation::from_geometry(socket_geom).domain_crossings;
+            res.socket_crossings = 0;
+        }
+    }
+    res
+}
+
+// A PALS mpiexec command line for a geometry, and any ways in which it differs
+// from the geometry
+fn pals_command(geom: Geometry, res: Reservation) -> (String, Vec<String>) {
+    let mut warnings = launcher_distribution_warnings("mpiexec", geom, res);
+    if geom.reserved_cores() > 0 {
+        warnings.push(String::from(
+            "mpiexec cannot reserve cores for the system, this must be requested from the scheduler",
+        ));
+    }
+    let options = [
+        format!("-n {}", geom.tasks),
+        format!("--ppn {}", res.max_node_tasks),
+        format!("--depth {}", geom.threads),
+        String::from("--cpu-bind depth"),
+    ];
+    (format!("mpiexec {}", options.join(" ")), warnings)
+}
+
+// Reporting as an Open MPI mpirun command line, with any alternate geometries
+// included as commented out commands
+pub fn openmpi_reporter(
+    geom: Geometry,
+    res: Reservation,
+    alternates: Vec<(Geometry, Reservation)>,
+) {
+    launcher_reporter(geom, res, alternates, &openmpi_command);
+}
+
+// Reporting as Intel MPI environment settings and an mpirun command line, with
+// any alternate geometries included as commented out commands
+pub fn intelmpi_reporter(
+    geom: Geometry,
+    res: Reservation,
+    alternates: Vec<(Geometry, Reservation)>,
+) {
+    launcher_reporter(geom, res, alternates, &intelmpi_command);
+}
+
+// Reporting as shell commands exporting the OpenMP settings for a geometry,
+// with any alternate geometries included as commented out commands
+pub fn env_reporter(
+    geom: Geometry,
+    res: Reservation,
+    alternates: Vec<(Geometry, Reservation)>,
+    stacksize: &str,
+) {
+    launcher_reporter(geom, res, alternates, &|g, r| env_command(g, r, stacksize));
+}
+
+// Reporting as variable settings for use in shell scripts and workflow
+// managers, with any alternate geometries included as variables with an index
+// suffix
+pub fn vars_reporter(
+    geom: Geometry,
+    res: Reservation,
+    alternates: Vec<(Geometry, Reservation)>,
+    prefix: &str,
+    style: VariableStyle,
+) {
+    let set = |name: &str, value: u32| match style {
+        VariableStyle::Sh => println!("{}{}={}", prefix, name, value),
+        VariableStyle::Csh => println!("set {}{}={}", prefix, name, value),
+        VariableStyle::Ecflow => println!("edit {}{} '{}'", prefix, name, value),
+    };
+    let set_job = |geom: Geometry, res: Reservation, suffix: &str| {
+        set(&format!("NODES{}", suffix), res.nodes);
+        set(&format!("TASKS{}", suffix), geom.tasks);
+        set(&format!("TASKS_PER_NODE{}", suffix), res.max_node_tasks);
+        set(&format!("THREADS{}", suffix), geom.threads);
+        set(&format!("CPUS{}", suffix), res.cpus);
+        set(&format!("IDLE_CPUS{}", suffix), res.idle_cpus);
+    };
+    for warning in warnings(res) {
+        println!("# warning: {}", warning);
+    }
+    set_job(geom, res, "");
+    set("ALTERNATES", alternates.len() as u32);
+    for (i, (g, r)) in alternates.into_iter().enumerate() {
+        set_job(g, r, &format!("_{}", i + 1));
+    }
+}
+
+// Reporting as an Open MPI rankfile binding each rank to the cores of the
+// named hosts, there must be at least one host per node
+pub fn rankfile_reporter(placement: &Placement, hosts: &[String]) {
+    let geom = placement.geometry();
+    let cores = geom.topology().cores();
+    for rank in placement.ranks() {
+        let mut rank_cores: Vec<u32> = rank.cpus.iter().map(|cpu| cpu % cores).collect();
+        rank_cores.sort_unstable();
+        rank_cores.dedup();
+        println!(
+            "rank {}={} slot={}",
+            rank.rank,
+            hosts[rank.node as usize],
+            cpu_list(&rank_cores)
+        );
+    }
+}
+
+// Reporting as an Open MPI hostfile giving the number of ranks on each of the
+// named hosts, there must be at least one host per node
+pub fn hostfile_reporter(geom: Geometry, hosts: &[String]) {
+    for (host, node) in hosts.iter().zip(Reservation::node_usage(geom)) {
+        println!("{} slots={}", host, node.tasks);
+    }
+}
+
+// An Open MPI mpirun command line for a geometry, and any ways in which it
+// differs from the geometry
+fn openmpi_command(geom: Geometry, res: Reservation) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    if geom.distribution() != Distribution::Packed && res.imbalance() > 0 {
+        warnings.push(format!(
+            "mpirun maps a fixed number of PEs per node, the {} distribution cannot be expressed",
+            geom.distribution()
+        ));
+    }
+    if geom.reserved_cores() > 0 {
+        warnings.push(String::from(
+            "mpirun cannot reserve cores for the system, reserved cores may be used",
+        ));
+    }
+    let mut options = vec![
+        format!("-n {}", geom.tasks),
+        format!(
+            "--map-by ppr:{}:node:PE={}",
+            res.max_node_tasks, geom.threads
+        ),
+    ];
+    if geom.distribution() == Distribution::Cyclic {
+        options.push(String::from("--rank-by node"));
+    }
+    if geom.smt() > 1 {
+        options.push(String::from("--use-hwthread-cpus --bind-to hwthread"));
+    } else {
+        options.push(String::from("--bind-to core"));
+    }
+    options.push(format!("-x OMP_NUM_THREADS={}", geom.threads));
+    (format!("mpirun {}", options.join(" ")), warnings)
+}
+
+// OpenMP environment settings for a geometry, and any ways in which the
+// threads of a PE will not be placed as the geometry describes
+fn env_command(geom: Geometry, _res: Reservation, stacksize: &str) -> (String, Vec<String>) {
+    let mut warnings = Vec::new();
+    // Each thread has its own logical CPU, so with SMT the places must be
+    // hardware threads rather than whole cores.
+    let places = if geom.smt() > 1 {
+        if geom.threads % geom.smt() != 0 {
+            warnings.push(format!(
+                "PEs of {} threads do not fill whole cores with {} logical CPUs per core",
+                geom.threads,
+                geom.smt()
+            ));
+        }
+        "threads"
+    } else {
+        "cores"
+    };
+    let lines = [
+        format!("export OMP_NUM_THREADS={}", geom.threads),
+        format!("export OMP_PLACES={}", places),
+        String::from("export OMP_PROC_BIND=close"),
+        format!("export OMP_STACKSIZE={}", stacksize),
+    ];
+    (lines.join("\n"), warnings)
+}
+
+// Intel MPI environment settings and mpirun command line for a geometry, and
+// any ways in which they differ from the geometry
+fn intelmpi_command(geom: Geometry, res: Reservation) -> (String, Vec<String>) {
+    let mut warnings = launcher_distribution_warnings("mpirun", geom, res);
+    if geom.reserved_cores() > 0 {
+        warnings.push(String::from(
+            "mpirun cannot reserve cores for the system, reserved cores may be used",
+        ));
+    }
+    let lines = [
+        format!("export I_MPI_PIN_DOMAIN={}:compact", geom.threads),
+        format!("export OMP_NUM_THREADS={}", geom.threads),
+        format!("mpirun -n {} -ppn {}", geom.tasks, res.max_node_tasks),
+    ];
+    (lines.join("\n"), warnings)
+}
+
+// Warnings for launchers that always pack PEs onto nodes in rank order
+fn launcher_distribution_warnings(launcher: &str, geom: Geometry, res: Reservation) -> Vec<String> {
+    match geom.distribution() {
+        Distribution::Cyclic if res.nodes > 1 => vec![format!(
+            "{} places consecutive PEs on the same node, the cyclic distribution cannot be expressed",
+            launcher
+        )],
+        Distribution::Balanced if res.imbalance() > 0 => vec![format!(
+            "{} packs PEs onto nodes, the balanced distribution cannot be expressed",
+            launcher
+        )],
+        _ => Vec::new(),
+    }
+}
+
+// Options describing the whole allocation, for use in an sbatch header
+fn slurm_job_options(geom: Geometry, res: Reservation) -> Vec<String> {
+    let mut options = vec![format!("--nodes={}", res.nodes)];
+    options.extend(slurm_step_options(geom, res));
+    if geom.reserved_cores() > 0 {
+        options.push(format!("--core-spec={}", geom.reserved_cores()));
+    }
+    options
+}
+
+// Options describing the layout of a job step, for use on an srun command line
+fn slurm_step_options(geom: Geometry, res: Reservation) -> Vec<String> {
+    let tasks_per_node = match geom.distribution() {
+        Distribution::Packed => geom.tasks_per_node(),
+        Distribution::Balanced | Distribution::Cyclic => res.max_node_tasks,
+    };
+    let distribution = match geom.distribution() {
+        Distribution::Packed | Distribution::Balanced => "block",
+        Distribution::Cyclic => "cyclic",
+    };
+    // Slurm does not allow --hint to be combined with --threads-per-core,
+    // so the hint is only used to disable multithreading.
+    let threading = if geom.smt() > 1 {
+        format!("--threads-per-core={}", geom.smt())
+    } else {
+        String::from("--hint=nomultithread")
+    };
+    vec![
+        format!("--ntasks={}", geom.tasks),
+        format!("--ntasks-per-node={}", tasks_per_node),
+        format!("--cpus-per-task={}", geom.threads),
+        threading,
+        format!("--distribution={}", distribution),
+    ]
+}
+
+// Reporting the logical CPUs each rank is bound to as a plain text table
+pub fn placement_reporter(placement: &Placement) {
+    println!(
+        "{:>8} {:>6} {:>6}  {:<24} mask",
+        "rank", "node", "local", "cpus"
+    );
+    for rank in placement.ranks() {
+        println!(
+            "{:>8} {:>6} {:>6}  {:<24} {}",
+            rank.rank,
+            rank.node,
+            rank.local_rank,
+            cpu_list(&rank.cpus),
+            rank.mask()
+        );
+    }
+}
+
+// Reporting the CPU masks of the ranks on each node in the form used by
+// Slurm's --cpu-bind option, and the OpenMP places of each local rank
+pub fn masks_reporter(placement: &Placement) {
+    let mut node = 0;
+    let mut layouts = Vec::new();
+    for run in Reservation::node_runs(placement.geometry()) {
+        layouts.push(placement.node(node));
+        node += run.count;
+    }
+    let masks: Vec<String> = layouts
+        .iter()
+        .max_by_key(|layout| layout.len())
+        .unwrap()
+        .iter()
+        .map(|rank| rank.mask())
+        .collect();
+    let consistent = layouts.iter().all(|layout| {
+        layout
+            .iter()
+            .zip(&masks)
+            .all(|(rank, mask)| rank.mask() == *mask)
+    });
+    if !consistent {
+        println!("# warning: nodes with fewer tasks use different masks, this binding cannot be expressed with a single mask list");
+    }
+    println!("--cpu-bind=mask_cpu:{}", masks.join(","));
+    for rank in layouts.iter().max_by_key(|layout| layout.len()).unwrap() {
+        println!(
+            "OMP_PLACES=\"{}\"  # local rank {}",
+            rank.places(),
+            rank.local_rank
+        );
+    }
+}
+
+// Reporting the result of a locate query in human-readable plain text
+pub fn locate_text_reporter(location: Location) {
+    match location {
+        Location::Rank(rank) => println!(
+            "PE {} runs on node {} (local rank {}), CPUs {}",
+            rank.rank,
+            rank.node,
+            rank.local_rank,
+            cpu_list(&rank.cpus)
+        ),
+        Location::Thread(thread) => println!(
+            "PE {} thread {} runs on node {}, CPU {}",
+            thread.rank, thread.thread, thread.node, thread.cpu
+        ),
+        Location::Cpu(node, cpu, CpuUse::Thread(thread)) => println!(
+            "node {} CPU {} runs PE {} thread {}",
+            node, cpu, thread.rank, thread.thread
+        ),
+        Location::Cpu(node, cpu, CpuUse::Reserved) => {
+            println!("node {} CPU {} is reserved for the system", node, cpu)
+        }
+        Location::Cpu(node, cpu, CpuUse::Idle) => println!("node {} CPU {} is idle", node, cpu),
+    }
+}
+
+// Reporting the result of a locate query in JSON format
+pub fn locate_json_reporter(location: Location) {
+    let report = match location {
+        Location::Rank(rank) => json!(rank),
+        Location::Thread(thread) => json!(thread),
+        Location::Cpu(node, cpu, usage) => {
+            let (state, thread) = match usage {
+                CpuUse::Thread(thread) => ("used", Some(thread)),
+                CpuUse::Reserved => ("reserved", None),
+                CpuUse::Idle => ("idle", None),
+            };
+            json!({
+                "node": node,
+                "cpu": cpu,
+                "state": state,
+                "rank": thread.map(|t| t.rank),
+                "thread": thread.map(|t| t.thread),
+            })
+        }
+    };
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+}
+
+// Reporting the check of a running job against its allocation in
+// human-readable plain text
+pub fn allocation_text_reporter(check: &AllocationCheck) {
+    let geom = check.geometry;
+    println!(
+        "{} job: {} x {} in an allocation of {} nodes",
+        check.scheduler, geom.tasks, geom.threads, check.nodes
+    );
+    text_reporter(None, Reservation::from_geometry(geom), Vec::new());
+    if check.mismatches.is_empty() {
+        println!("the job matches its allocation");
+    }
+    for mismatch in &check.mismatches {
+        println!("mismatch: {}", mismatch);
+    }
+}
+
+// Reporting the check of a running job against its allocation in JSON format
+pub fn allocation_json_reporter(check: &AllocationCheck) {
+    let res = Reservation::from_geometry(check.geometry);
+    let report = json!({
+        "scheduler": check.scheduler,
+        "geometry": check.geometry,
+        "reservation": res,
+        "allocation": {
+            "nodes": check.nodes,
+            "cpus_on_node": check.cpus_on_node,
+            "omp_threads": check.omp_threads,
+        },
+        "warnings": warnings(res),
+        "mismatches": check.mismatches,
+    });
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+}
+
+// Reporting the evaluation of a geometry on several machines as a plain text
+// table
+pub fn compare_text_reporter(results: &[MachineResult]) {
+    let width = results
+        .iter()
+        .map(|result| result.name.len())
+        .max()
+        .unwrap_or(0)
+        .max(7);
+    println!(
+        "{:<width$} {:>6} {:>8} {:>8}  best alternate",
+        "machine",
+        "nodes",
+        "cores",
+        "idle",
+        width = width
+    );
+    for result in results {
+        let best = match result.best {
+            Some((g, r)) => format!(
+                "{} x {} ({} nodes; {} CPU cores)",
+                g.tasks, g.threads, r.nodes, r.cpus
+            ),
+            None => String::from("-"),
+        };
+        println!(
+            "{:<width$} {:>6} {:>8} {:>8}  {}",
+            result.name,
+            result.res.nodes,
+            result.res.cpus,
+            result.res.idle_cpus,
+            best,
+            width = width
+        );
+    }
+}
+
+// Reporting the evaluation of a geometry on several machines in JSON format,
+// as an object keyed by machine name
+pub fn compare_json_reporter(results: &[MachineResult]) {
+    let report: serde_json::Map<String, serde_json::Value> = results
+        .iter()
+        .map(|result| {
+            let value = json!({
+                "geometry": result.geom,
+                "reservation": result.res,
+                "warnings": warnings(result.res),
+                "best_alternative": result
+                    .best
+                    .map(|(g, r)| json!({"geometry": g, "reservation": r})),
+            });
+            (result.name.clone(), value)
+        })
+        .collect();
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+}
+
+// Reporting the geometries proposed for a machine that match a geometry on
+// another machine in human-readable plain text
+pub fn translate_text_reporter(
+    source: &MachineResult,
+    machine: &str,
+    geometries: Vec<(Geometry, Reservation)>,
+) {
+    println!(
+        "{}: {} x {} ({} nodes; {} CPU cores)",
+        source.name, source.geom.tasks, source.geom.threads, source.res.nodes, source.res.cpus
+    );
+    if geometries.is_empty() {
+        println!(
+            "no geometries found for {} that fill the reservation",
+            machine
+        );
+        return;
+    }
+    println!("geometries for {} that fill the reservation:", machine);
+    for (g, r) in geometries {
+        println!(
+            "  {} x {} ({} nodes; {} CPU cores)",
+            g.tasks, g.threads, r.nodes, r.cpus
+        );
+    }
+}
+
+// Reporting the geometries proposed for a machine that match a geometry on
+// another machine in JSON format
+pub fn translate_json_reporter(
+    source: &MachineResult,
+    machine: &str,
+    geometries: Vec<(Geometry, Reservation)>,
+) {
+    let report = json!({
+        "from": {
+            "machine": source.name,
+            "geometry": source.geom,
+            "reservation": source.res,
+        },
+        "to": {
+            "machine": machine,
+            "geometries": geometries
+                .iter()
+                .map(|&(g, r)| json!({"geometry": g, "reservation": r}))
+                .collect::<Vec<serde_json::Value>>(),
+        },
+    });
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+}
+
+// Reporting the effective configuration as a plain text table
+pub fn config_text_reporter(files: &[String], settings: &[Setting]) {
+    print_config_files(files);
+    let width = settings
+        .iter()
+        .map(|setting| setting.key.len())
+        .max()
+        .unwrap_or(0)
+        .max(7);
+    let values: Vec<String> = settings
+        .iter()
+        .map(|setting| match &setting.value {
+            serde_json::Value::String(s) => s.clone(),
+            serde_json::Value::Null => String::from("-"),
+            value => value.to_string(),
+        })
+        .collect();
+    let value_width = values.iter().map(|v| v.len()).max().unwrap_or(0).max(5);
+    println!(
+        "{:<width$}  {:<value_width$}  source",
+        "setting",
+        "value",
+        width = width,
+        value_width = value_width
+    );
+    for (setting, value) in settings.iter().zip(values) {
+        println!(
+            "{:<width$}  {:<value_width$}  {}",
+            setting.key,
+            value,
+            setting.source,
+            width = width,
+            value_width = value_width
+        );
+    }
+}
+
+// Reporting the effective configuration in JSON format, with the settings
+// keyed by name
+pub fn config_json_reporter(files: &[String], settings: &[Setting]) {
+    let settings: serde_json::Map<String, serde_json::Value> = settings
+        .iter()
+        .map(|setting| {
+            let value = json!({
+                "value": setting.value,
+                "source": setting.source,
+            });
+            (setting.key.clone(), value)
+        })
+        .collect();
+    let report = json!({
+        "files": files,
+        "settings": settings,
+    });
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+}
+
+// Reporting the configuration files read when they contain an error, in
+// human-readable plain text, the error itself is reported by the caller
+pub fn config_error_text_reporter(files: &[String]) {
+    print_config_files(files);
+}
+
+// Reporting the configuration files read and the error in them in JSON format
+pub fn config_error_json_reporter(files: &[String], error: &ConfigError) {
+    let report = json!({
+        "files": files,
+        "error": error.to_string(),
+    });
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+}
+
+// The list of configuration files read, in order of increasing precedence
+fn print_config_files(files: &[String]) {
+    if files.is_empty() {
+        println!("configuration files: none");
+    } else {
+        println!("configuration files: {}", files.join(", "));
+    }
+}
+
+// Reporting the results of an audit as a plain text table
+pub fn audit_text_reporter(results: &[AuditResult]) {
+    let width = results
+        .iter()
+        .map(|result| match result {
+            AuditResult::Job(file, _) | AuditResult::Error(file, _) => file.len(),
+        })
+        .max()
+        .unwrap_or(0)
+        .max(6);
+    println!(
+        "{:<width$} {:>6} {:<7} {:>14} {:>6} {:>8} {:>8}",
+        "script",
+        "line",
+        "source",
+        "geometry",
+        "nodes",
+        "cores",
+        "idle",
+        width = width
+    );
+    for result in results {
+        match result {
+            AuditResult::Job(file, job) => {
+                let geom = job.geometry;
+                let res = Reservation::from_geometry(geom);
+                println!(
+                    "{:<width$} {:>6} {:<7} {:>14} {:>6} {:>8} {:>8}",
+                    file,
+                    job.line,
+                    job.source,
+                    format!("{} x {}", geom.tasks, geom.threads),
+                    res.nodes,
+                    res.cpus,
+                    res.idle_cpus,
+                    width = width
+                );
+            }
+            AuditResult::Error(file, message) => {
+                println!("{:<width$} error: {}", file, message, width = width)
+            }
+        }
+    }
+}
+
+// Reporting the results of an audit in JSON format
+pub fn audit_json_reporter(results: &[AuditResult]) {
+    let report: Vec<serde_json::Value> = results
+        .iter()
+        .map(|result| match result {
+            AuditResult::Job(file, job) => {
+                let res = Reservation::from_geometry(job.geometry);
+                json!({
+                    "script": file,
+                    "line": job.line,
+                    "source": job.source,
+                    "geometry": job.geometry,
+                    "reservation": res,
+                    "warnings": warnings(res),
+                })
+            }
+            AuditResult::Error(file, message) => json!({"script": file, "error": message}),
+        })
+        .collect();
+    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+}
+
+// A list of logical CPUs in thread order, with ascending runs shortened to ranges
+fn cpu_list(cpus: &[u32]) -> String {
+    let mut ranges: Vec<(u32, u32)> = Vec::new();
+    for &cpu in cpus {
+        match ranges.last_mut() {
+            Some((_, last)) if *last + 1 == cpu => *last = cpu,
+            _ => ranges.push((cpu, cpu)),
+        }
+    }
+    ranges
+        .iter()
+        .map(|&(first, last)| {
+            if first == last {
+                format!("{}", first)
+            } else {
+                format!("{}-{}", first, last)
+            }
+        })
+        .collect::<Vec<String>>()
+        .join(",")
+}
+
+// Warnings about a reservation that are common to all reporters
+fn warnings(res: Reservation) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if !res.is_filled() {
+        warnings.push(String::from("reservation is not filled"));
+    }
+    warnings.extend(crossing_warnings(res));
+    warnings
+}
+
+fn crossing_warnings(res: Reservation) -> Vec<String> {
+    let mut warnings = Vec::new();
+    if res.socket_crossings > 0 {
+        warnings.push(format!(
+            "{} tasks have threads spread across more than one socket",
+            res.socket_crossings
+        ));
+    }
+    if res.domain_crossings > 0 {
+        warnings.push(format!(
+            "{} tasks have threads spread across more than one NUMA domain",
+            res.domain_crossings
+        ));
+    }
+    warnings
+}
@@ -1,77 +1,672 @@
-use regex::Regex;
+use std::fmt;
 
 use crate::config::SearchConfig;
+use pestr::{Geometry, Reservation};
 
-#[derive(Debug)]
-pub struct SearchOptions {
-    pub conserve_nodes: bool,
-    pub pe_radius: f32,
-    pub thread_radius: f32,
+// The most numbers that a range in the search options can cover.
+const MAX_RANGE: u32 = 10000;
+
+// A set of numbers of PEs, threads or nodes that a search is restricted to.
+// The ends of a range that are not given are taken from the search radius.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub enum CountSet {
+    #[default]
+    Any,
+    Range(Option<u32>, Option<u32>),
+    Values(Vec<u32>),
 }
 
-impl SearchOptions {
-    pub fn default(search_config: SearchConfig) -> Self {
-        Self {
-            conserve_nodes: search_config.conserve_nodes,
-            pe_radius: search_config.pe_radius,
-            thread_radius: search_config.thread_radius,
+impl CountSet {
+    // Parse a number, a range such as 96..160 or 96.., a bound such as <=20,
+    // or a set such as {4,8,16}.
+    fn parse(text: &str) -> Option<Self> {
+        let number = |s: &str| s.trim().parse::<u32>().ok();
+        let set = if text == "any" {
+            CountSet::Any
+        } else if let Some(values) = text.strip_prefix('{').and_then(|t| t.strip_suffix('}')) {
+            let values: Option<Vec<u32>> = values.split(',').map(number).collect();
+            CountSet::Values(values.filter(|v| !v.is_empty())?)
+        } else if let Some(n) = text.strip_prefix("<=") {
+            CountSet::Range(None, Some(number(n)?))
+        } else if let Some(n) = text.strip_prefix(">=") {
+            CountSet::Range(Some(number(n)?), None)
+        } else if let Some(n) = text.strip_prefix('<') {
+            CountSet::Range(None, Some(number(n)?.checked_sub(1)?))
+        } else if let Some(n) = text.strip_prefix('>') {
+            CountSet::Range(Some(number(n)?.checked_add(1)?), None)
+        } else if let Some((min, max)) = text.split_once("..") {
+            let min = if min.is_empty() {
+                None
+            } else {
+                Some(number(min)?)
+            };
+            let max = if max.is_empty() {
+                None
+            } else {
+                Some(number(max)?)
+            };
+            match (min, max) {
+                (None, None) => return None,
+                (Some(min), Some(max)) if min > max => return None,
+                _ => CountSet::Range(min, max),
+            }
+        } else {
+            let n = number(text)?;
+            CountSet::Range(Some(n), Some(n))
+        };
+        Some(set)
+    }
+
+    // The counts to search for the option `name`, given the count in the
+    // geometry and the search radius for it.
+    fn candidates(&self, name: &str, count: u32, radius: f32) -> Result<Vec<u32>, String> {
+        let range = Geometry::radius_range(count, radius);
+        match self {
+            CountSet::Any => Ok(range.collect()),
+            CountSet::Range(min, max) => {
+                let (min, max) = (min.unwrap_or(*range.start()), max.unwrap_or(*range.end()));
+                if min > max {
+                    return Err(format!(
+                        "nothing to search for {}={}, which is outside the range {}..{} \
+                         given by the search radius",
+                        name,
+                        self,
+                        range.start(),
+                        range.end()
+                    ));
+                }
+                if max - min >= MAX_RANGE {
+                    return Err(format!(
+                        "too many values to search for {}={}, a range can have at most {}",
+                        name, self, MAX_RANGE
+                    ));
+                }
+                Ok((min..=max).collect())
+            }
+            CountSet::Values(values) => Ok(values.clone()),
         }
     }
 
-    pub fn parse(s: &str, search_config: SearchConfig) -> Result<Self, String> {
-        let pe_radius_matcher = FloatOption::new("pe_radius");
-        let thread_radius_matcher = FloatOption::new("thread_radius");
+    // The counts in both this set and `other`, `None` if there are none.
+    fn intersect(&self, other: &CountSet) -> Option<CountSet> {
+        let set = match (self, other) {
+            (CountSet::Any, set) | (set, CountSet::Any) => set.clone(),
+            (&CountSet::Range(min, max), &CountSet::Range(other_min, other_max)) => {
+                let min = min.max(other_min);
+                let max = match (max, other_max) {
+                    (Some(max), Some(other_max)) => Some(max.min(other_max)),
+                    _ => max.or(other_max),
+                };
+                if min.zip(max).is_some_and(|(min, max)| min > max) {
+                    return None;
+                }
+                CountSet::Range(min, max)
+            }
+            (CountSet::Values(values), set) | (set, CountSet::Values(values)) => {
+                let values: Vec<u32> = values
+                    .iter()
+                    .copied()
+                    .filter(|&v| set.contains(v))
+                    .collect();
+                if values.is_empty() {
+                    return None;
+                }
+                CountSet::Values(values)
+            }
+        };
+        Some(set)
+    }
 
-        let mut conserve_nodes = search_config.conserve_nodes;
-        let mut pe_radius = search_config.pe_radius;
-        let mut thread_radius = search_config.thread_radius;
+    fn contains(&self, count: u32) -> bool {
+        match self {
+            CountSet::Any => true,
+            CountSet::Range(min, max) => {
+                min.map_or(true, |min| count >= min) && max.map_or(true, |max| count <= max)
+            }
+            CountSet::Values(values) => values.contains(&count),
+        }
+    }
+}
 
-        for opt in s.split(',') {
-            if opt == "conserve_nodes" {
-                conserve_nodes = true;
-            } else if pe_radius_matcher.is_match(opt) {
-                pe_radius = pe_radius_matcher.get_value(opt);
-            } else if thread_radius_matcher.is_match(opt) {
-                thread_radius = thread_radius_matcher.get_value(opt);
-            } else {
-                return Err(format!("unknown search option: {}", opt));
+impl fmt::Display for CountSet {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CountSet::Any => write!(f, "any"),
+            CountSet::Range(Some(min), Some(max)) if min == max => write!(f, "{}", min),
+            CountSet::Range(min, max) => {
+                if let Some(min) = min {
+                    write!(f, "{}", min)?;
+                }
+                write!(f, "..")?;
+                if let Some(max) = max {
+                    write!(f, "{}", max)?;
+                }
+                Ok(())
+            }
+            CountSet::Values(values) => {
+                let values: Vec<String> = values.iter().map(|v| v.to_string()).collect();
+                write!(f, "{{{}}}", values.join(","))
+            }
+        }
+    }
+}
+
+// The parts of the given geometry that alternate geometries must keep.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Locks {
+    pub pes: bool,
+    pub threads: bool,
+    pub nodes: bool,
+}
+
+impl Locks {
+    // Parse "none", or one or more of pes, threads and nodes joined with +.
+    fn parse(text: &str) -> Option<Self> {
+        let mut locks = Locks::default();
+        if text == "none" {
+            return Some(locks);
+        }
+        for name in text.split('+') {
+            match name {
+                "pes" => locks.pes = true,
+                "threads" => locks.threads = true,
+                "nodes" => locks.nodes = true,
+                _ => return None,
+            }
+        }
+        Some(locks)
+    }
+}
+
+impl fmt::Display for Locks {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let names: Vec<&str> = [
+            (self.pes, "pes"),
+            (self.threads, "threads"),
+            (self.nodes, "nodes"),
+        ]
+        .iter()
+        .filter(|(locked, _)| *locked)
+        .map(|&(_, name)| name)
+        .collect();
+        if names.is_empty() {
+            write!(f, "none")
+        } else {
+            write!(f, "{}", names.join("+"))
+        }
+    }
+}
+
+// The value of a search option.
+#[derive(Clone, Debug, PartialEq)]
+pub enum OptionValue {
+    Flag(bool),
+    Fraction(f32),
+    Counts(CountSet),
+    Lock(Locks),
+    Limit(Option<u32>),
+}
+
+impl OptionValue {
+    pub fn to_json(&self) -> serde_json::Value {
+        match self {
+            // A number with the same decimal digits as the setting, rather than
+            // those of its exact value as a double.
+            OptionValue::Fraction(x) => serde_json::json!(x.to_string().parse::<f64>().unwrap()),
+            OptionValue::Flag(b) => serde_json::json!(b),
+            OptionValue::Limit(n) => serde_json::json!(n),
+            OptionValue::Counts(_) | OptionValue::Lock(_) => serde_json::json!(self.to_string()),
+        }
+    }
+
+    // The value as it is written in a configuration file.
+    pub fn to_toml(&self) -> String {
+        match self {
+            OptionValue::Flag(_) | OptionValue::Fraction(_) | OptionValue::Limit(Some(_)) => {
+                self.to_string()
+            }
+            _ => format!("\"{}\"", self),
+        }
+    }
+}
+
+impl fmt::Display for OptionValue {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OptionValue::Flag(b) => write!(f, "{}", b),
+            OptionValue::Fraction(x) => write!(f, "{}", x),
+            OptionValue::Counts(set) => write!(f, "{}", set),
+            OptionValue::Lock(locks) => write!(f, "{}", locks),
+            OptionValue::Limit(Some(n)) => write!(f, "{}", n),
+            OptionValue::Limit(None) => write!(f, "none"),
+        }
+    }
+}
+
+// The kinds of value a search option may take.
+#[derive(Clone, Copy, Debug)]
+enum Kind {
+    Flag,
+    Fraction,
+    Counts,
+    Lock,
+    Limit,
+}
+
+impl Kind {
+    fn description(self) -> &'static str {
+        match self {
+            Kind::Flag => "true or false",
+            Kind::Fraction => "a non-negative number",
+            Kind::Counts => {
+                "a number, a range such as 96..160, a bound such as <=20, or a set such as {4,8,16}"
             }
+            Kind::Lock => "pes, threads or nodes, or several of them joined with +",
+            Kind::Limit => "a positive whole number or none",
         }
+    }
+}
+
+// A setting tuning the search for alternate geometries. Every search option
+// can be given in the --search string, in the search section of a
+// configuration file, and in a PESTR_SEARCH_<NAME> environment variable.
+pub struct SearchOption {
+    pub name: &'static str,
+    kind: Kind,
+    pub help: &'static str,
+    default: fn() -> OptionValue,
+    get: fn(&SearchConfig) -> OptionValue,
+    set: fn(&mut SearchConfig, OptionValue),
+}
+
+pub static SEARCH_OPTIONS: &[SearchOption] = &[
+    SearchOption {
+        name: "pe_radius",
+        kind: Kind::Fraction,
+        help: "the search radius for PEs, as a fraction of the given number of PEs",
+        default: || OptionValue::Fraction(0.25),
+        get: |search| OptionValue::Fraction(search.pe_radius),
+        set: |search, value| {
+            if let OptionValue::Fraction(x) = value {
+                search.pe_radius = x;
+            }
+        },
+    },
+    SearchOption {
+        name: "thread_radius",
+        kind: Kind::Fraction,
+        help: "the search radius for threads, as a fraction of the given number of threads",
+        default: || OptionValue::Fraction(0.5),
+        get: |search| OptionValue::Fraction(search.thread_radius),
+        set: |search, value| {
+            if let OptionValue::Fraction(x) = value {
+                search.thread_radius = x;
+            }
+        },
+    },
+    SearchOption {
+        name: "conserve_nodes",
+        kind: Kind::Flag,
+        help: "require alternate geometries to use the same number of nodes as the given geometry",
+        default: || OptionValue::Flag(false),
+        get: |search| OptionValue::Flag(search.conserve_nodes),
+        set: |search, value| {
+            if let OptionValue::Flag(b) = value {
+                search.conserve_nodes = b;
+            }
+        },
+    },
+    SearchOption {
+        name: "pes",
+        kind: Kind::Counts,
+        help: "the numbers of PEs to search, the ends of a range that are not given \
+               are taken from pe_radius",
+        default: || OptionValue::Counts(CountSet::Any),
+        get: |search| OptionValue::Counts(search.pes.clone()),
+        set: |search, value| {
+            if let OptionValue::Counts(set) = value {
+                search.pes = set;
+            }
+        },
+    },
+    SearchOption {
+        name: "threads",
+        kind: Kind::Counts,
+        help: "the numbers of threads to search, the ends of a range that are not \
+               given are taken from thread_radius",
+        default: || OptionValue::Counts(CountSet::Any),
+        get: |search| OptionValue::Counts(search.threads.clone()),
+        set: |search, value| {
+            if let OptionValue::Counts(set) = value {
+                search.threads = set;
+            }
+        },
+    },
+    SearchOption {
+        name: "nodes",
+        kind: Kind::Counts,
+        help: "the numbers of nodes alternate geometries may use",
+        default: || OptionValue::Counts(CountSet::Any),
+        get: |search| OptionValue::Counts(search.nodes.clone()),
+        set: |search, value| {
+            if let OptionValue::Counts(set) = value {
+                search.nodes = set;
+            }
+        },
+    },
+    SearchOption {
+        name: "lock",
+        kind: Kind::Lock,
+        help: "keep the number of PEs, threads or nodes of the given geometry",
+        default: || OptionValue::Lock(Locks::default()),
+        get: |search| OptionValue::Lock(search.lock),
+        set: |search, value| {
+            if let OptionValue::Lock(locks) = value {
+                search.lock = locks;
+            }
+        },
+    },
+    SearchOption {
+        name: "max",
+        kind: Kind::Limit,
+        help: "the largest number of alternate geometries to suggest, keeping those \
+               closest in size to the given geometry",
+        default: || OptionValue::Limit(None),
+        get: |search| OptionValue::Limit(search.max),
+        set: |search, value| {
+            if let OptionValue::Limit(max) = value {
+                search.max = max;
+            }
+        },
+    },
+];
 
-        Ok(Self {
-            conserve_nodes,
-            pe_radius,
-            thread_radius,
+// The search option with the given name.
+pub fn find(name: &str) -> Option<&'static SearchOption> {
+    SEARCH_OPTIONS.iter().find(|option| option.name == name)
+}
+
+impl SearchOption {
+    // The key for the option in a configuration file.
+    pub fn key(&self) -> String {
+        format!("search.{}", self.name)
+    }
+
+    // The environment variable that sets the option.
+    pub fn env_name(&self) -> String {
+        format!("PESTR_SEARCH_{}", self.name.to_uppercase())
+    }
+
+    pub fn get(&self, search: &SearchConfig) -> OptionValue {
+        (self.get)(search)
+    }
+
+    pub fn set(&self, search: &mut SearchConfig, value: OptionValue) {
+        (self.set)(search, value)
+    }
+
+    // Parse a value for the option, `None` if the option is given on its own,
+    // which sets a flag.
+    pub fn parse(&self, text: Option<&str>) -> Result<OptionValue, String> {
+        let text = match (self.kind, text) {
+            (Kind::Flag, None) => return Ok(OptionValue::Flag(true)),
+            (_, None) => {
+                return Err(format!(
+                    "search option {} needs a value, expected {}",
+                    self.name,
+                    self.kind.description()
+                ))
+            }
+            (_, Some(text)) => text,
+        };
+        let value = match self.kind {
+            Kind::Flag => text.parse().ok().map(OptionValue::Flag),
+            Kind::Fraction => text
+                .parse::<f32>()
+                .ok()
+                .filter(|x| x.is_finite() && *x >= 0.0)
+                .map(OptionValue::Fraction),
+            Kind::Counts => CountSet::parse(text).map(OptionValue::Counts),
+            Kind::Lock => Locks::parse(text).map(OptionValue::Lock),
+            Kind::Limit if text == "none" => Some(OptionValue::Limit(None)),
+            Kind::Limit => text
+                .parse::<u32>()
+                .ok()
+                .filter(|&n| n > 0)
+                .map(|n| OptionValue::Limit(Some(n))),
+        };
+        value.ok_or_else(|| {
+            format!(
+                "invalid value '{}' for search option {}, expected {}",
+                text,
+                self.name,
+                self.kind.description()
+            )
         })
     }
+
+    // Convert a value for the option from a configuration file, sets of counts
+    // and several locks may also be given as arrays.
+    pub fn parse_toml(&self, value: &toml::Value) -> Result<OptionValue, String> {
+        let text = match (self.kind, value) {
+            (_, toml::Value::Boolean(b)) => b.to_string(),
+            (_, toml::Value::Integer(i)) => i.to_string(),
+            (_, toml::Value::Float(x)) => x.to_string(),
+            (Kind::Counts | Kind::Lock | Kind::Limit, toml::Value::String(s)) => s.clone(),
+            (Kind::Counts, toml::Value::Array(items)) => {
+                let items: Vec<String> = items.iter().map(|item| item.to_string()).collect();
+                format!("{{{}}}", items.join(","))
+            }
+            (Kind::Lock, toml::Value::Array(items)) => {
+                let items: Vec<&str> = items
+                    .iter()
+                    .map(|item| item.as_str().unwrap_or("?"))
+                    .collect();
+                items.join("+")
+            }
+            _ => {
+                return Err(format!(
+                    "invalid value for search option {}, expected {}",
+                    self.name,
+                    self.kind.description()
+                ))
+            }
+        };
+        self.parse(Some(&text))
+    }
 }
 
-struct FloatOption {
-    regex: Regex,
+impl SearchConfig {
+    // The search settings with every option at its default value.
+    pub fn defaults() -> Self {
+        let mut search = Self::default();
+        for option in SEARCH_OPTIONS {
+            option.set(&mut search, (option.default)());
+        }
+        search
+    }
+
+    // Alternate geometries to `geom` that fill whole nodes, are within these
+    // search settings and are accepted by `filter`.
+    pub fn alternates(
+        &self,
+        geom: Geometry,
+        filter: &dyn Fn(Geometry, Reservation) -> bool,
+    ) -> Result<Vec<(Geometry, Reservation)>, String> {
+        let res = Reservation::from_geometry(geom);
+        let tasks = if self.lock.pes {
+            vec![geom.tasks]
+        } else {
+            self.pes.candidates("pes", geom.tasks, self.pe_radius)?
+        };
+        let threads = if self.lock.threads {
+            vec![geom.threads]
+        } else {
+            self.threads
+                .candidates("threads", geom.threads, self.thread_radius)?
+        };
+        let keep_nodes = self.conserve_nodes || self.lock.nodes;
+        let search_filter = |g, r: Reservation| {
+            (!keep_nodes || r.nodes == res.nodes) && self.nodes.contains(r.nodes) && filter(g, r)
+        };
+        Ok(geom.alternates_among(&tasks, &threads, &search_filter))
+    }
+
+    // The `max` geometries closest in size to `cpus` CPU cores, preferring
+    // fewer nodes, kept in their given order. All of them if there is no limit.
+    pub fn limit(
+        &self,
+        cpus: u32,
+        geometries: Vec<(Geometry, Reservation)>,
+    ) -> Vec<(Geometry, Reservation)> {
+        let max = match self.max {
+            Some(max) => max as usize,
+            None => return geometries,
+        };
+        let mut closest: Vec<usize> = (0..geometries.len()).collect();
+        closest.sort_by_key(|&i| {
+            let (g, r) = geometries[i];
+            ((g.tasks * g.threads).abs_diff(cpus), r.nodes)
+        });
+        closest.truncate(max);
+        closest.sort_unstable();
+        closest.into_iter().map(|i| geometries[i]).collect()
+    }
 }
 
-impl FloatOption {
-    fn new(option_name: &str) -> Self {
-        Self {
-            regex: Regex::new(&format!(
-                "^{}={}$",
-                regex::escape(option_name),
-                r"(?P<value>[0-9]*\.?[0-9]+)"
+// Parse a comma-separated list of search options, each one NAME=VALUE, a bound
+// such as NAME<=VALUE, or just NAME for a flag.
+pub fn parse(s: &str) -> Result<Vec<(&'static SearchOption, OptionValue)>, String> {
+    let names: Vec<&str> = SEARCH_OPTIONS.iter().map(|option| option.name).collect();
+    let mut options: Vec<(&'static SearchOption, OptionValue)> = Vec::new();
+    for (start, token) in tokens(s)? {
+        let at = |message: String| {
+            format!(
+                "{}, in '{}' at character {} of the search options",
+                message,
+                token,
+                position(s, start)
+            )
+        };
+        let name_end = token.find(['=', '<', '>']).unwrap_or(token.len());
+        let (name, rest) = token.split_at(name_end);
+        if name.is_empty() {
+            return Err(at(String::from("missing search option name")));
+        }
+        let option = find(name).ok_or_else(|| {
+            at(format!(
+                "unknown search option '{}', expected one of {}",
+                name,
+                names.join(", ")
             ))
-            .unwrap(),
+        })?;
+        let text = match rest.strip_prefix('=') {
+            Some(value) => Some(value),
+            None if rest.is_empty() => None,
+            None => Some(rest),
+        };
+        let value = option.parse(text).map_err(at)?;
+        // A number of PEs, threads or nodes given more than once is restricted
+        // to the counts allowed by every value, so bounds can be combined.
+        match options.iter_mut().find(|(o, _)| o.name == option.name) {
+            None => options.push((option, value)),
+            Some((_, OptionValue::Counts(previous))) => {
+                let counts = match &value {
+                    OptionValue::Counts(counts) => counts,
+                    _ => unreachable!(),
+                };
+                *previous = previous.intersect(counts).ok_or_else(|| {
+                    at(format!(
+                        "search option {} does not overlap its earlier value {}",
+                        option.name, previous
+                    ))
+                })?;
+            }
+            Some(_) => {
+                return Err(at(format!(
+                    "search option {} is given more than once",
+                    option.name
+                )))
+            }
+        }
+    }
+    Ok(options)
+}
+
+// Split a --search string at the commas that are not inside braces, giving
+// the byte offset of each option.
+fn tokens(s: &str) -> Result<Vec<(usize, &str)>, String> {
+    let mut tokens = Vec::new();
+    let mut start = 0;
+    let mut open_brace = None;
+    for (i, c) in s.char_indices() {
+        match (c, open_brace) {
+            ('{', None) => open_brace = Some(i),
+            ('}', Some(_)) => open_brace = None,
+            ('{', Some(_)) | ('}', None) => {
+                return Err(format!(
+                    "unexpected '{}' at character {} of the search options",
+                    c,
+                    position(s, i)
+                ))
+            }
+            (',', None) => {
+                tokens.push((start, &s[start..i]));
+                start = i + 1;
+            }
+            _ => {}
         }
     }
+    if let Some(i) = open_brace {
+        return Err(format!(
+            "unclosed '{{' at character {} of the search options",
+            position(s, i)
+        ));
+    }
+    tokens.push((start, &s[start..]));
+    Ok(tokens)
+}
 
-    fn is_match(&self, text: &str) -> bool {
-        self.regex.is_match(text)
+// The 1-based character position of a byte offset into a string.
+fn position(s: &str, offset: usize) -> usize {
+    s[..offset].chars().count() + 1
+}
+
+// The search settings with the options in a --search string taking precedence
+// over `search`.
+pub fn apply(s: &str, mut search: SearchConfig) -> Result<SearchConfig, String> {
+    for (option, value) in parse(s)? {
+        option.set(&mut search, value);
     }
+    Ok(search)
+}
 
-    fn get_value(&self, text: &str) -> f32 {
-        self.regex
-            .captures(text)
-            .and_then(|c| c.name("value").map(|m| m.as_str()))
-            .unwrap()
-            .parse()
-            .unwrap()
+// The long help for the --search option.
+pub fn help() -> String {
+    let mut help = String::from(
+        "Suggest alternative geometries that fill whole nodes, the option can be \
+         specified on its own, or with an argument. On its own it will perform a \
+         search within parameters from the configuration, or using default \
+         parameters if none are configured. If an argument is given it is a \
+         comma-separated list of search options, NAME=VALUE, a bound such as \
+         NAME<=VALUE, or just NAME to set a flag, for example \
+         'pes=96..160,threads={4,8,16},nodes<=20,max=10'. Bounds on the same \
+         number can be combined, as in 'nodes>=10,nodes<=20'. The search options \
+         are:\n",
+    );
+    for option in SEARCH_OPTIONS {
+        help.push_str(&format!(
+            "\n  {}: {}, {} (default {})",
+            option.name,
+            option.help,
+            option.kind.description(),
+            (option.default)()
+        ));
     }
+    help.push_str(
+        "\n\nEach search option can also be set in the [search] section of a \
+         configuration file, or with a PESTR_SEARCH_<NAME> environment variable, \
+         for example PESTR_SEARCH_PE_RADIUS.",
+    );
+    help
 }